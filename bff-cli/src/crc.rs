@@ -1,6 +1,8 @@
 use std::io::{self, BufRead as _, Read as _};
+use std::ops::ControlFlow;
 
 use bff::crc::{
+    ReversibleCrc32,
     asobo_alternate32_options,
     asobo32_options,
     asobo64_options,
@@ -13,6 +15,8 @@ use clap::ValueEnum;
 
 use crate::error::BffCliResult;
 
+pub const DEFAULT_REVERSE_CHARSET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_";
+
 #[derive(ValueEnum, Clone, Copy)]
 pub enum CrcAlgorithm {
     #[value(alias("a"))]
@@ -33,6 +37,18 @@ pub enum CrcAlgorithm {
     MQFEL32,
 }
 
+impl CrcAlgorithm {
+    const fn reversible(self) -> Option<ReversibleCrc32> {
+        match self {
+            Self::Asobo => Some(ReversibleCrc32::Asobo32),
+            Self::AsoboAlternate => Some(ReversibleCrc32::AsoboAlternate32),
+            Self::Kalisto => Some(ReversibleCrc32::Kalisto32),
+            Self::BlackSheep => Some(ReversibleCrc32::BlackSheep32),
+            _ => None,
+        }
+    }
+}
+
 #[derive(ValueEnum, Clone, Copy)]
 pub enum CrcMode {
     Bytes,
@@ -135,3 +151,73 @@ pub fn crc(
     }
     Ok(())
 }
+
+// Accepts signed, unsigned, or 0x prefixed hexadecimal hashes
+fn parse_hash32(string: &str) -> Option<i32> {
+    if let Some(hex) = string
+        .strip_prefix("0x")
+        .or_else(|| string.strip_prefix("0X"))
+    {
+        return u32::from_str_radix(hex, 16).ok().map(|hash| hash as i32);
+    }
+    string
+        .parse::<i32>()
+        .ok()
+        .or_else(|| string.parse::<u32>().ok().map(|hash| hash as i32))
+}
+
+pub struct ReverseOptions {
+    pub prefix: String,
+    pub charset: String,
+    pub max_length: usize,
+    pub max_results: Option<usize>,
+}
+
+pub fn reverse(
+    hash: &str,
+    options: &ReverseOptions,
+    starting: i64,
+    algorithm: CrcAlgorithm,
+    format: CrcFormat,
+) -> BffCliResult<()> {
+    let reversible = algorithm.reversible().ok_or_else(|| {
+        io::Error::other(
+            "`--reverse` only supports the 32-bit Asobo, Kalisto, and BlackSheep hashes",
+        )
+    })?;
+    let target = parse_hash32(hash)
+        .ok_or_else(|| io::Error::other(format!("`{hash}` is not a 32-bit hash")))?;
+    if !options.charset.is_ascii() {
+        return Err(io::Error::other("`--charset` may only contain ASCII characters").into());
+    }
+
+    let expected = reversible.expected_suffixes(options.charset.as_bytes(), options.max_length);
+    if expected > 1.0 {
+        eprintln!(
+            "About {expected:.0} suffixes are expected to match by chance, most will be collisions"
+        );
+    }
+
+    let mut remaining = options.max_results.unwrap_or(usize::MAX);
+    reversible.forge_suffixes(
+        options.prefix.as_bytes(),
+        target,
+        starting as i32,
+        options.charset.as_bytes(),
+        options.max_length,
+        |suffix| {
+            if remaining == 0 {
+                return ControlFlow::Break(());
+            }
+            remaining -= 1;
+            println!(
+                r#"{} "{}{}""#,
+                format_hash(target, format),
+                options.prefix,
+                String::from_utf8_lossy(suffix)
+            );
+            ControlFlow::Continue(())
+        },
+    );
+    Ok(())
+}
@@ -5,15 +5,12 @@ use std::path::Path;
 
 use bff::bigfile::BigFile;
 use bff::bigfile::manifest::Manifest;
-use bff::bigfile::resource::bff_resource::BffResource;
 use bff::bigfile::versions::Version;
-use bff::class::bff_class::BffClass;
 use bff::names::NameContext;
-use bff::traits::Import as _;
 use indicatif::{ProgressBar, ProgressStyle};
 
 use crate::error::{BffCliError, BffCliResult};
-use crate::shared::{read_artifacts, resolve_platform, resource_json_path, write_names};
+use crate::shared::{read_resource_path, resolve_platform, write_names};
 
 pub fn create(
     directory: &Path,
@@ -51,28 +48,7 @@ pub fn create(
     for file in std::fs::read_dir(resources_path)? {
         let path = file?.path();
         progress_bar.inc(1);
-        if path.is_file() {
-            let mut file_reader = BufReader::new(File::open(&path)?);
-            let BffResource { resource, .. } = BffResource::read(&mut file_reader, &name_context)?;
-            if resources.contains_key(&resource.name) {
-                return Err(crate::error::BffCliError::DuplicateResource {
-                    name: resource.name,
-                });
-            }
-            resources.insert(resource.name, resource);
-        } else if path.is_dir() {
-            let directory = path;
-            let resource_serialized_path = resource_json_path(&directory);
-            let resource_serialized_reader = BufReader::new(File::open(resource_serialized_path)?);
-            let mut bff_class: BffClass =
-                bff::names::json::from_reader(resource_serialized_reader, &mut name_context)?;
-
-            let artifacts = read_artifacts(&directory)?;
-
-            let _ = bff_class.class.import(&artifacts);
-
-            let BffResource { resource, .. } = bff_class.bff_resource(&name_context)?;
-
+        if let Some(resource) = read_resource_path(&path, &mut name_context)? {
             if resources.contains_key(&resource.name) {
                 return Err(crate::error::BffCliError::DuplicateResource {
                     name: resource.name,
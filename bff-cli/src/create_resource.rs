@@ -5,10 +5,9 @@ use std::path::Path;
 use bff::BufReader;
 use bff::class::bff_class::BffClass;
 use bff::names::NameContext;
-use bff::traits::Import as _;
 
 use crate::error::BffCliResult;
-use crate::shared::{read_artifacts, resource_json_path, write_names};
+use crate::shared::{import_artifacts, read_artifacts, resource_json_path, write_names};
 
 pub fn create_resource(
     directory: &Path,
@@ -26,7 +25,7 @@ pub fn create_resource(
 
     let artifacts = read_artifacts(directory)?;
 
-    let _ = bff_class.class.import(&artifacts);
+    import_artifacts(&mut bff_class.class, &artifacts, &mut name_context)?;
 
     let bff_resource = bff_class.bff_resource(&name_context)?;
 
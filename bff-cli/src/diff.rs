@@ -1,19 +1,106 @@
-use std::collections::BTreeMap;
+use std::cmp::max;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
 use std::io::Write as _;
+use std::ops::Range;
 use std::path::Path;
 
 use bff::BufReader;
 use bff::bigfile::BigFile;
+use bff::bigfile::resource::bff_resource::BffResourceRef;
 use bff::bigfile::resource::{Resource, ResourceData};
 use bff::names::NameContext;
+use serde_json::{Value, json};
 
-use crate::error::BffCliResult;
+use crate::error::{BffCliError, BffCliResult};
 use crate::shared::{probe_bigfile_name_context, read_bigfile, read_bigfile_names};
 
+const MAX_FIELD_CHANGES: usize = 256;
+const MAX_HEXDIFF_RANGES: usize = 16;
+const MAX_HEXDIFF_BYTES: usize = 32;
+// Differing bytes closer than this are reported as a single range
+const HEXDIFF_MERGE_GAP: usize = 8;
+
 struct ResolvedResource<'a> {
     link_name: Option<String>,
-    resource: &'a Resource,
+    bff_resource: BffResourceRef<'a>,
+}
+
+enum Change {
+    Link {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    Field {
+        path: String,
+        old: Option<Value>,
+        new: Option<Value>,
+    },
+    Bytes {
+        part: &'static str,
+        range: Range<usize>,
+        old: String,
+        new: String,
+    },
+    DataFormat(String),
+    Truncated(usize),
+}
+
+impl Change {
+    fn describe(&self) -> String {
+        match self {
+            Self::Link { old, new } => format!(
+                "link: {} -> {}",
+                display_link_name(old.as_deref()),
+                display_link_name(new.as_deref())
+            ),
+            Self::Field { path, old, new } => format!(
+                "{path}: {} -> {}",
+                display_value(old.as_ref()),
+                display_value(new.as_ref())
+            ),
+            Self::Bytes {
+                part,
+                range,
+                old,
+                new,
+            } => format!(
+                "{part}[{:#x}..{:#x}]: {} -> {}",
+                range.start,
+                range.end,
+                display_hex(old),
+                display_hex(new)
+            ),
+            Self::DataFormat(description) => description.clone(),
+            Self::Truncated(count) => format!("... {count} more changes"),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            Self::Link { old, new } => json!({ "kind": "link", "old": old, "new": new }),
+            Self::Field { path, old, new } => {
+                json!({ "kind": "field", "path": path, "old": old, "new": new })
+            }
+            Self::Bytes {
+                part,
+                range,
+                old,
+                new,
+            } => json!({
+                "kind": "bytes",
+                "part": part,
+                "offset": range.start,
+                "size": range.len(),
+                "old": old,
+                "new": new,
+            }),
+            Self::DataFormat(description) => {
+                json!({ "kind": "data_format", "description": description })
+            }
+            Self::Truncated(count) => json!({ "kind": "truncated", "count": count }),
+        }
+    }
 }
 
 fn read_name_file(name_path: &Path, name_context: &mut NameContext) -> BffCliResult<()> {
@@ -61,7 +148,7 @@ fn resolve_resources<'a>(
                 .link_name
                 .as_ref()
                 .map(|name| name.with_context(name_context).to_string()),
-            resource: bff_resource.resource,
+            bff_resource,
         };
 
         assert!(
@@ -79,45 +166,30 @@ fn display_link_name(link_name: Option<&str>) -> &str {
     link_name.unwrap_or("<none>")
 }
 
-fn describe_size_change(label: &str, old_size: usize, new_size: usize) -> String {
-    if old_size == new_size {
-        format!("{label} changed ({old_size} bytes)")
-    } else {
-        format!("{label}: {old_size} -> {new_size} bytes")
-    }
+fn display_value(value: Option<&Value>) -> String {
+    value.map_or_else(|| "<none>".to_owned(), Value::to_string)
 }
 
-fn describe_split_part_size_change(label: &str, old_size: usize, new_size: usize) -> String {
-    if old_size == new_size {
-        format!("{label}: {old_size} bytes")
-    } else {
-        format!("{label}: {old_size} -> {new_size} bytes")
+fn display_hex(hex: &str) -> &str {
+    if hex.is_empty() { "<none>" } else { hex }
+}
+
+fn to_hex(data: &[u8], range: &Range<usize>) -> String {
+    let start = range.start.min(data.len());
+    let end = range.end.min(data.len());
+    let mut hex: String = data[start..end]
+        .iter()
+        .take(MAX_HEXDIFF_BYTES)
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    if end - start > MAX_HEXDIFF_BYTES {
+        hex.push_str("..");
     }
+    hex
 }
 
-fn describe_data_change(old_resource: &Resource, new_resource: &Resource) -> String {
+fn describe_data_format_change(old_resource: &Resource, new_resource: &Resource) -> String {
     match (&old_resource.data, &new_resource.data) {
-        (ResourceData::Data(old_data), ResourceData::Data(new_data)) => {
-            describe_size_change("data", old_data.len(), new_data.len())
-        }
-        (
-            ResourceData::SplitData {
-                link_header: old_link_header,
-                body: old_body,
-            },
-            ResourceData::SplitData {
-                link_header: new_link_header,
-                body: new_body,
-            },
-        ) => format!(
-            "split data ({}, {})",
-            describe_split_part_size_change(
-                "link_header",
-                old_link_header.len(),
-                new_link_header.len()
-            ),
-            describe_split_part_size_change("body", old_body.len(), new_body.len())
-        ),
         (ResourceData::Data(old_data), ResourceData::SplitData { link_header, body }) => format!(
             "data format: Data ({} bytes) -> SplitData (link_header: {} bytes, body: {} bytes)",
             old_data.len(),
@@ -130,28 +202,147 @@ fn describe_data_change(old_resource: &Resource, new_resource: &Resource) -> Str
             body.len(),
             new_data.len()
         ),
+        _ => "data format unchanged".to_owned(),
+    }
+}
+
+fn diff_bytes(part: &'static str, old: &[u8], new: &[u8], changes: &mut Vec<Change>) {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for i in (0..max(old.len(), new.len())).filter(|i| old.get(*i) != new.get(*i)) {
+        match ranges.last_mut() {
+            Some(range) if i - range.end < HEXDIFF_MERGE_GAP => range.end = i + 1,
+            _ => ranges.push(i..i + 1),
+        }
+    }
+
+    let range_count = ranges.len();
+    changes.extend(
+        ranges
+            .into_iter()
+            .take(MAX_HEXDIFF_RANGES)
+            .map(|range| Change::Bytes {
+                part,
+                old: to_hex(old, &range),
+                new: to_hex(new, &range),
+                range,
+            }),
+    );
+    if range_count > MAX_HEXDIFF_RANGES {
+        changes.push(Change::Truncated(range_count - MAX_HEXDIFF_RANGES));
     }
 }
 
+fn diff_data(old_resource: &Resource, new_resource: &Resource, changes: &mut Vec<Change>) {
+    match (&old_resource.data, &new_resource.data) {
+        (ResourceData::Data(old_data), ResourceData::Data(new_data)) => {
+            diff_bytes("data", old_data, new_data, changes);
+        }
+        (
+            ResourceData::SplitData {
+                link_header: old_link_header,
+                body: old_body,
+            },
+            ResourceData::SplitData {
+                link_header: new_link_header,
+                body: new_body,
+            },
+        ) => {
+            diff_bytes("link_header", old_link_header, new_link_header, changes);
+            diff_bytes("body", old_body, new_body, changes);
+        }
+        _ => changes.push(Change::DataFormat(describe_data_format_change(
+            old_resource,
+            new_resource,
+        ))),
+    }
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn diff_values(path: &str, old: Option<&Value>, new: Option<&Value>, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                diff_values(&join_path(path, key), old.get(key), new.get(key), changes);
+            }
+        }
+        (Some(Value::Array(old)), Some(Value::Array(new))) => {
+            for i in 0..max(old.len(), new.len()) {
+                diff_values(&format!("{path}[{i}]"), old.get(i), new.get(i), changes);
+            }
+        }
+        _ if old != new => changes.push(Change::Field {
+            path: path.to_owned(),
+            old: old.cloned(),
+            new: new.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+fn class_value(resource: &ResolvedResource<'_>, name_context: &NameContext) -> Option<Value> {
+    let bff_class = resource.bff_resource.bff_class(name_context).ok()?;
+    let mut value = bff::names::json::to_value(&bff_class.class, name_context).ok()?;
+    // Skip the class and version enum tags so paths start at the class fields
+    for _ in 0..2 {
+        let Value::Object(object) = &mut value else {
+            return None;
+        };
+        if object.len() != 1 {
+            return None;
+        }
+        value = object.values_mut().next()?.take();
+    }
+    Some(value)
+}
+
 fn describe_changes(
     old_resource: &ResolvedResource<'_>,
     new_resource: &ResolvedResource<'_>,
-) -> Vec<String> {
+    old_name_context: &NameContext,
+    new_name_context: &NameContext,
+) -> Vec<Change> {
     let mut changes = Vec::new();
 
     if old_resource.link_name != new_resource.link_name {
-        changes.push(format!(
-            "link: {} -> {}",
-            display_link_name(old_resource.link_name.as_deref()),
-            display_link_name(new_resource.link_name.as_deref())
-        ));
+        changes.push(Change::Link {
+            old: old_resource.link_name.clone(),
+            new: new_resource.link_name.clone(),
+        });
     }
 
-    if old_resource.resource.data != new_resource.resource.data {
-        changes.push(describe_data_change(
-            old_resource.resource,
-            new_resource.resource,
-        ));
+    let old_data = &old_resource.bff_resource.resource.data;
+    let new_data = &new_resource.bff_resource.resource.data;
+    if old_data != new_data {
+        match (
+            class_value(old_resource, old_name_context),
+            class_value(new_resource, new_name_context),
+        ) {
+            (Some(old_value), Some(new_value)) if old_value != new_value => {
+                let mut field_changes = Vec::new();
+                diff_values("", Some(&old_value), Some(&new_value), &mut field_changes);
+                let field_change_count = field_changes.len();
+                field_changes.truncate(MAX_FIELD_CHANGES);
+                changes.append(&mut field_changes);
+                if field_change_count > MAX_FIELD_CHANGES {
+                    changes.push(Change::Truncated(field_change_count - MAX_FIELD_CHANGES));
+                }
+            }
+            // Resources that don't parse, or only differ in bytes the classes don't keep, get
+            // a hexdiff instead
+            _ => diff_data(
+                old_resource.bff_resource.resource,
+                new_resource.bff_resource.resource,
+                &mut changes,
+            ),
+        }
     }
 
     changes
@@ -162,6 +353,7 @@ pub fn diff(
     new_bigfile_path: &Path,
     old_name_path: Option<&Path>,
     new_name_path: Option<&Path>,
+    json: bool,
 ) -> BffCliResult<()> {
     let (old_bigfile, old_name_context) = load_bigfile(old_bigfile_path, old_name_path)?;
     let (new_bigfile, new_name_context) = load_bigfile(new_bigfile_path, new_name_path)?;
@@ -183,13 +375,46 @@ pub fn diff(
         .iter()
         .filter_map(|(name, old_resource)| {
             let new_resource = new_resources.get(name)?;
-            let changes = describe_changes(old_resource, new_resource);
-            (!changes.is_empty()).then(|| format!("{name} ({})", changes.join(", ")))
+            let changes = describe_changes(
+                old_resource,
+                new_resource,
+                &old_name_context,
+                &new_name_context,
+            );
+            (!changes.is_empty()).then_some((name, changes))
         })
         .collect::<Vec<_>>();
 
+    let difference_count = added.len() + removed.len() + changed.len();
     let mut stdout = std::io::stdout().lock();
 
+    if json {
+        let changed = changed
+            .iter()
+            .map(|(name, changes)| {
+                (
+                    (*name).clone(),
+                    changes.iter().map(Change::to_json).collect::<Value>(),
+                )
+            })
+            .collect::<serde_json::Map<_, _>>();
+        serde_json::to_writer_pretty(
+            &mut stdout,
+            &json!({ "added": added, "removed": removed, "changed": changed }),
+        )?;
+        writeln!(stdout)?;
+        return differences_result(difference_count);
+    }
+
+    let changed = changed
+        .iter()
+        .flat_map(|(name, changes)| {
+            changes
+                .iter()
+                .map(move |change| format!("{name}: {}", change.describe()))
+        })
+        .collect::<Vec<_>>();
+
     if added.is_empty() && removed.is_empty() && changed.is_empty() {
         writeln!(stdout, "No differences found.")?;
         return Ok(());
@@ -210,5 +435,14 @@ pub fn diff(
         }
     }
 
-    Ok(())
+    differences_result(difference_count)
+}
+
+// Like diff(1), differing BigFiles are an error so scripts can check the exit status
+const fn differences_result(count: usize) -> BffCliResult<()> {
+    if count == 0 {
+        Ok(())
+    } else {
+        Err(BffCliError::DifferencesFound { count })
+    }
 }
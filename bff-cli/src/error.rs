@@ -13,6 +13,30 @@ pub enum BffCliError {
     DuplicateResource {
         name: Name,
     },
+    #[display("Could not find resource with name {}", name)]
+    MissingResource {
+        name: String,
+    },
+    #[display("Invalid name value {}", value)]
+    #[from(ignore)]
+    InvalidNameValue {
+        value: String,
+    },
+    #[display("{} block checksums do not match", count)]
+    #[from(ignore)]
+    ChecksumMismatch {
+        count: usize,
+    },
+    #[display("{} resources differ", count)]
+    #[from(ignore)]
+    DifferencesFound {
+        count: usize,
+    },
+    #[display("Found more than one artifact with the same name as {}", path.display())]
+    #[from(ignore)]
+    DuplicateArtifact {
+        path: std::path::PathBuf,
+    },
     #[display("BigFile has no extension: {}", path.display())]
     MissingBigFileExtension {
         path: std::path::PathBuf,
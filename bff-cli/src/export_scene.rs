@@ -0,0 +1,52 @@
+use std::path::{Path, PathBuf};
+
+use bff::scene;
+use indicatif::ProgressBar;
+
+use crate::error::{BffCliError, BffCliResult};
+use crate::shared::{
+    probe_bigfile_name_context,
+    read_bigfile,
+    read_bigfile_names,
+    read_in_names,
+    write_artifacts,
+};
+
+pub fn export_scene(
+    bigfile_path: &Path,
+    directory: &Path,
+    in_names: &[PathBuf],
+    worlds: &[String],
+    world_transforms: bool,
+) -> BffCliResult<()> {
+    let mut name_context = probe_bigfile_name_context(bigfile_path)?;
+    let progress_bar = ProgressBar::new_spinner();
+    progress_bar.set_message("Reading names");
+    read_bigfile_names(bigfile_path, &mut name_context)?;
+    read_in_names(in_names, &mut name_context)?;
+
+    progress_bar.set_message("Reading BigFile");
+    let bigfile = read_bigfile(bigfile_path, &name_context)?;
+
+    let world_names = worlds
+        .iter()
+        .map(|world| {
+            bigfile
+                .resource_names()
+                .find(|name| name.with_context(&name_context).to_string() == *world)
+                .ok_or_else(|| BffCliError::MissingResource {
+                    name: world.clone(),
+                })
+        })
+        .collect::<BffCliResult<Vec<_>>>()?;
+
+    progress_bar.set_message("Exporting scene");
+    let artifacts = scene::export_scene(&bigfile, &world_names, &name_context, world_transforms)?;
+
+    std::fs::create_dir_all(directory)?;
+    write_artifacts(directory, artifacts)?;
+
+    progress_bar.finish_and_clear();
+
+    Ok(())
+}
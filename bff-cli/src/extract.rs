@@ -1,20 +1,25 @@
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::BufWriter;
 use std::path::{Path, PathBuf};
 
+use bff::BufReader;
+use bff::bigfile::lazy::LazyBigFile;
 use bff::bigfile::resource::bff_resource::BffResourceRef;
-use bff::names::NameContext;
+use bff::names::{Name, NameContext};
+use bff::petgraph::visit::Dfs;
 use bff::traits::Export as _;
 use clap::ValueEnum;
 use indicatif::{ProgressBar, ProgressStyle};
-use rayon::iter::{ParallelBridge as _, ParallelIterator as _};
+use rayon::iter::{IntoParallelIterator as _, ParallelIterator as _};
 
 use crate::error::{BffCliError, BffCliResult};
 use crate::shared::{
+    dds_artifacts_to_png,
     probe_bigfile_name_context,
-    read_bigfile,
     read_bigfile_names,
     read_in_names,
+    read_lazy_bigfile,
     resource_json_path,
     write_artifacts,
 };
@@ -27,6 +32,111 @@ pub enum ExportStrategy {
     Rich,
 }
 
+// Each kind of filter is optional. A resource is extracted if it matches at least one value of
+// every kind that is given.
+pub struct ExtractFilter {
+    pub names: Vec<String>,
+    pub classes: Vec<String>,
+    pub globs: Vec<String>,
+    pub reachable_from: Vec<String>,
+}
+
+impl ExtractFilter {
+    const fn is_empty(&self) -> bool {
+        self.names.is_empty()
+            && self.classes.is_empty()
+            && self.globs.is_empty()
+            && self.reachable_from.is_empty()
+    }
+
+    fn select(
+        &self,
+        bigfile: &LazyBigFile<BufReader<File>>,
+        name_context: &NameContext,
+    ) -> Vec<Name> {
+        let reachable = (!self.reachable_from.is_empty())
+            .then(|| reachable_names(bigfile, &self.reachable_from, name_context));
+
+        bigfile
+            .resource_names()
+            .filter(|name| {
+                let name_string = name.with_context(name_context).to_string();
+                let class_name_string = bigfile
+                    .class_name(*name)
+                    .unwrap_or_default()
+                    .with_context(name_context)
+                    .to_string();
+
+                (self.names.is_empty() || self.names.contains(&name_string))
+                    && (self.classes.is_empty()
+                        || self
+                            .classes
+                            .iter()
+                            .any(|class| class_matches(class, &class_name_string)))
+                    && (self.globs.is_empty()
+                        || self.globs.iter().any(|glob| glob_match(glob, &name_string)))
+                    && reachable
+                        .as_ref()
+                        .is_none_or(|reachable| reachable.contains(name))
+            })
+            .collect()
+    }
+}
+
+// Class names may be given with or without the _Z suffix
+fn class_matches(class: &str, class_name: &str) -> bool {
+    class_name == class || class_name.strip_suffix("_Z") == Some(class)
+}
+
+// Matches `*` against any run of characters and `?` against any single character
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let mut p = 0;
+    let mut t = 0;
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, star_t)) => {
+                    p = star + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star, t));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn reachable_names(
+    bigfile: &LazyBigFile<BufReader<File>>,
+    roots: &[String],
+    name_context: &NameContext,
+) -> HashSet<Name> {
+    let graph = bigfile.reference_graph(name_context);
+    let mut reachable = HashSet::new();
+    for root in graph
+        .node_indices()
+        .filter(|node| roots.contains(&graph[*node].with_context(name_context).to_string()))
+    {
+        let mut dfs = Dfs::new(&graph, root);
+        while let Some(node) = dfs.next(&graph) {
+            reachable.insert(graph[node]);
+        }
+    }
+    reachable
+}
+
 const INVALID_PATH_CHARS: [u8; 41] = [
     0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
     26, 27, 28, 29, 30, 31, 34, 42, 47, 58, 60, 62, 63, 92, 124,
@@ -86,6 +196,7 @@ fn export_bff_resource(
     bff_resource: &BffResourceRef,
     name_context: &NameContext,
     rich_suffix: &str,
+    png: bool,
 ) -> BffCliResult<()> {
     let bff_class = bff_resource.bff_class(name_context)?;
 
@@ -115,7 +226,10 @@ fn export_bff_resource(
     let resource_serialized_writer = BufWriter::new(File::create(resource_serialized_path)?);
     bff::names::json::to_writer_pretty(resource_serialized_writer, &bff_class, name_context)?;
 
-    if let Ok(artifacts) = bff_class.class.export() {
+    if let Ok(mut artifacts) = name_context.scope(|| bff_class.class.export()) {
+        if png {
+            artifacts = dds_artifacts_to_png(artifacts);
+        }
         write_artifacts(&directory, artifacts)?;
     }
 
@@ -128,6 +242,8 @@ pub fn extract(
     in_names: &[PathBuf],
     export_strategy: ExportStrategy,
     rich_suffix: &str,
+    filter: &ExtractFilter,
+    png: bool,
 ) -> BffCliResult<()> {
     let mut name_context = probe_bigfile_name_context(bigfile_path)?;
     let progress_bar = ProgressBar::new_spinner();
@@ -136,35 +252,55 @@ pub fn extract(
     read_in_names(in_names, &mut name_context)?;
 
     progress_bar.set_message("Reading BigFile");
-    let bigfile = read_bigfile(bigfile_path, &name_context)?;
+    let bigfile = read_lazy_bigfile(bigfile_path, &name_context)?;
+
+    progress_bar.set_message("Selecting resources");
+    let selected = filter.select(&bigfile, &name_context);
 
     progress_bar.set_message("Writing manifest");
     std::fs::create_dir_all(directory)?;
 
+    // A filtered extraction gets a manifest of just the extracted resources so it can be
+    // created again
     let manifest_path = directory.join("manifest.json");
     let manifest_writer = BufWriter::new(File::create(manifest_path)?);
-    bff::names::json::to_writer_pretty(manifest_writer, bigfile.manifest(), &name_context)?;
+    if filter.is_empty() {
+        bff::names::json::to_writer_pretty(manifest_writer, bigfile.manifest(), &name_context)?;
+    } else {
+        let selected_set = selected.iter().copied().collect::<HashSet<_>>();
+        let manifest = bigfile
+            .manifest()
+            .filtered(|name| selected_set.contains(&name));
+        bff::names::json::to_writer_pretty(manifest_writer, &manifest, &name_context)?;
+    }
 
     progress_bar.set_style(ProgressStyle::default_bar());
-    progress_bar.set_length(bigfile.bff_resources().len() as u64);
+    progress_bar.set_length(selected.len() as u64);
 
     let resources_path = directory.join("resources");
     std::fs::create_dir(&resources_path)?;
 
-    bigfile
-        .bff_resources()
-        .par_bridge()
-        .try_for_each(|bff_resource| {
-            progress_bar.inc(1);
-            if !matches!(export_strategy, ExportStrategy::Rich)
-                || export_bff_resource(&resources_path, &bff_resource, &name_context, rich_suffix)
-                    .is_err()
-            {
-                dump_bff_resource(&resources_path, &bff_resource, &name_context)?;
-            }
+    selected.into_par_iter().try_for_each(|name| {
+        progress_bar.inc(1);
+        let Some(resource) = bigfile.resource(name, &name_context)? else {
+            return Ok(());
+        };
+        let bff_resource = bigfile.bff_resource(&resource);
+        if !matches!(export_strategy, ExportStrategy::Rich)
+            || export_bff_resource(
+                &resources_path,
+                &bff_resource,
+                &name_context,
+                rich_suffix,
+                png,
+            )
+            .is_err()
+        {
+            dump_bff_resource(&resources_path, &bff_resource, &name_context)?;
+        }
 
-            Ok::<(), BffCliError>(())
-        })?;
+        Ok::<(), BffCliError>(())
+    })?;
 
     progress_bar.finish_and_clear();
 
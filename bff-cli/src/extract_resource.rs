@@ -8,12 +8,13 @@ use bff::names::NameContext;
 use bff::traits::Export as _;
 
 use crate::error::BffCliResult;
-use crate::shared::{read_in_names, resource_json_path, write_artifacts};
+use crate::shared::{dds_artifacts_to_png, read_in_names, resource_json_path, write_artifacts};
 
 pub fn extract_resource(
     resource_path: &Path,
     directory: &Path,
     in_names: &[PathBuf],
+    png: bool,
 ) -> BffCliResult<()> {
     let mut probe_reader = BufReader::new(File::open(resource_path)?);
     let name_type = BffResourceHeader::probe_name_type(&mut probe_reader)?;
@@ -32,7 +33,10 @@ pub fn extract_resource(
     let resource_serialized_writer = BufWriter::new(File::create(resource_serialized_path)?);
     bff::names::json::to_writer_pretty(resource_serialized_writer, &bff_class, &name_context)?;
 
-    if let Ok(artifacts) = bff_class.class.export() {
+    if let Ok(mut artifacts) = name_context.scope(|| bff_class.class.export()) {
+        if png {
+            artifacts = dds_artifacts_to_png(artifacts);
+        }
         write_artifacts(directory, artifacts)?;
     }
 
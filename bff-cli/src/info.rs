@@ -2,21 +2,30 @@ use std::fs::File;
 use std::io::{self, BufWriter, Write as _};
 use std::path::{Path, PathBuf};
 
+use bff::BufReader;
+use bff::bigfile::BigFile;
 use bff::petgraph::dot::{Config, Dot};
 
-use crate::error::BffCliResult;
-use crate::shared::{probe_bigfile_name_context, read_bigfile, read_bigfile_names, read_in_names};
+use crate::error::{BffCliError, BffCliResult};
+use crate::shared::{
+    probe_bigfile_name_context,
+    read_bigfile_names,
+    read_in_names,
+    read_lazy_bigfile,
+    resolve_platform,
+};
 
 pub fn info(
     bigfile_path: &Path,
     in_names: &[PathBuf],
     out_reference_graph: Option<&Path>,
+    verify_checksums: bool,
 ) -> BffCliResult<()> {
     let mut name_context = probe_bigfile_name_context(bigfile_path)?;
     read_bigfile_names(bigfile_path, &mut name_context)?;
     read_in_names(in_names, &mut name_context)?;
 
-    let bigfile = read_bigfile(bigfile_path, &name_context)?;
+    let bigfile = read_lazy_bigfile(bigfile_path, &name_context)?;
     bff::names::json::to_writer_pretty(io::stdout().lock(), bigfile.manifest(), &name_context)?;
 
     if let Some(out_reference_graph) = out_reference_graph {
@@ -27,5 +36,22 @@ pub fn info(
         name_context.scope(|| write!(&mut writer, "{:?}", dot))?;
     }
 
+    if verify_checksums {
+        let platform = resolve_platform(bigfile_path)?;
+        let mut reader = BufReader::new(File::open(bigfile_path)?);
+        let mismatches = BigFile::verify_checksums(&mut reader, platform, &name_context)?;
+        for mismatch in mismatches.iter() {
+            eprintln!(
+                "block {}: stored checksum {:#010X} does not match calculated checksum {:#010X}",
+                mismatch.block_index, mismatch.stored, mismatch.calculated
+            );
+        }
+        if !mismatches.is_empty() {
+            return Err(BffCliError::ChecksumMismatch {
+                count: mismatches.len(),
+            });
+        }
+    }
+
     Ok(())
 }
@@ -3,11 +3,12 @@ use std::path::PathBuf;
 use bff::bigfile::versions::Version;
 use bff::names::NameType;
 use clap::*;
-use crc::{CrcAlgorithm, CrcFormat, CrcMode};
+use crc::{CrcAlgorithm, CrcFormat, CrcMode, ReverseOptions};
 use crypt::CryptAlgorithm;
 use error::BffCliResult;
-use extract::ExportStrategy;
+use extract::{ExportStrategy, ExtractFilter};
 use lz::LzEndian;
+use names::CrackSources;
 
 use crate::lz::LzAlgorithm;
 
@@ -19,6 +20,7 @@ mod crypt;
 mod diff;
 mod dump_json_schema;
 mod error;
+mod export_scene;
 mod extract;
 mod extract_resource;
 mod fat_lin;
@@ -26,6 +28,7 @@ mod info;
 mod lz;
 mod mqfel_settings_bin;
 mod names;
+mod patch;
 mod psc;
 mod shared;
 mod stdio_or_path;
@@ -54,6 +57,16 @@ enum Commands {
         export_strategy: ExportStrategy,
         #[arg(long, default_value_t = String::from(".d"))]
         rich_suffix: String,
+        #[arg(long = "name")]
+        names: Vec<String>,
+        #[arg(long = "class")]
+        classes: Vec<String>,
+        #[arg(long = "glob")]
+        globs: Vec<String>,
+        #[arg(long)]
+        reachable_from: Vec<String>,
+        #[arg(long, help = "Export textures as PNG instead of DDS")]
+        png: bool,
     },
     #[clap(alias = "c")]
     Create {
@@ -66,12 +79,55 @@ enum Commands {
         #[arg(short, long)]
         tag: Option<String>,
     },
+    #[clap(alias = "p")]
+    Patch {
+        bigfile: PathBuf,
+        directory: Option<PathBuf>,
+        #[arg(
+            short,
+            long,
+            help = "Write the patched BigFile here instead of in place"
+        )]
+        out_bigfile: Option<PathBuf>,
+        #[arg(long)]
+        in_names: Vec<PathBuf>,
+        #[arg(long)]
+        out_names: Option<PathBuf>,
+        #[arg(short, long, help = "Name of a resource to remove before patching")]
+        remove: Vec<String>,
+        #[arg(
+            short,
+            long,
+            help = "Block to append new resources to, defaults to the last"
+        )]
+        block: Option<usize>,
+    },
+    #[clap(alias = "xs")]
+    ExportScene {
+        bigfile: PathBuf,
+        directory: PathBuf,
+        #[arg(long)]
+        in_names: Vec<PathBuf>,
+        #[arg(
+            short,
+            long = "world",
+            help = "Name of a World to export, defaults to every World in the BigFile"
+        )]
+        worlds: Vec<String>,
+        #[arg(
+            long,
+            help = "Use the Node world transformation matrices instead of their local transforms"
+        )]
+        world_transforms: bool,
+    },
     #[clap(alias = "xr")]
     ExtractResource {
         resource: PathBuf,
         directory: PathBuf,
         #[arg(long)]
         in_names: Vec<PathBuf>,
+        #[arg(long, help = "Export textures as PNG instead of DDS")]
+        png: bool,
     },
     #[clap(alias = "cr")]
     CreateResource {
@@ -87,6 +143,11 @@ enum Commands {
         in_names: Vec<PathBuf>,
         #[arg(long)]
         out_reference_graph: Option<PathBuf>,
+        #[arg(
+            long,
+            help = "Report blocks whose stored checksum does not match their data"
+        )]
+        verify_checksums: bool,
     },
     Diff {
         old_bigfile: PathBuf,
@@ -95,8 +156,13 @@ enum Commands {
         old_names: Option<PathBuf>,
         #[arg(long)]
         new_names: Option<PathBuf>,
+        #[arg(long)]
+        json: bool,
     },
+    #[command(args_conflicts_with_subcommands = true)]
     Names {
+        #[command(subcommand)]
+        command: Option<NamesCommands>,
         bigfile: Option<PathBuf>,
         #[arg(long)]
         name_type: Option<NameType>,
@@ -108,6 +174,30 @@ enum Commands {
         out_names: Option<PathBuf>,
         #[arg(short, long)]
         reference_graph: bool,
+        #[arg(
+            long,
+            help = "Resolve unknown names from the strings in resources and scripts"
+        )]
+        harvest: bool,
+        #[arg(
+            long = "harvest-script",
+            help = "Only used by --harvest. Extracted script file or directory to harvest"
+        )]
+        harvest_scripts: Vec<PathBuf>,
+        #[arg(
+            long,
+            help = "List the names that more than one of the read strings hash to"
+        )]
+        collisions: bool,
+        #[arg(
+            long = "prefer-source",
+            help = "Name file whose strings win collisions, in priority order"
+        )]
+        prefer_sources: Vec<PathBuf>,
+        #[arg(long, help = "Follow every written name with the source of its string")]
+        annotate_sources: bool,
+        #[arg(long, help = "Directory to write one name file per source to")]
+        out_names_by_source: Option<PathBuf>,
     },
     Crc {
         string: Option<String>,
@@ -127,6 +217,34 @@ enum Commands {
         #[clap(value_enum)]
         #[arg(short, long, default_value_t = CrcFormat::Signed)]
         format: CrcFormat,
+        #[arg(
+            long,
+            help = "List the suffixes of --prefix that hash to this value instead of hashing"
+        )]
+        reverse: Option<String>,
+        #[arg(
+            long,
+            default_value_t,
+            help = "Only used by --reverse. Known start of the string"
+        )]
+        prefix: String,
+        #[arg(
+            long,
+            default_value = crc::DEFAULT_REVERSE_CHARSET,
+            help = "Only used by --reverse. Characters the suffix may contain"
+        )]
+        charset: String,
+        #[arg(
+            long,
+            default_value_t = 6,
+            help = "Only used by --reverse. Maximum length of the suffix"
+        )]
+        max_length: usize,
+        #[arg(
+            long,
+            help = "Only used by --reverse. Stop after printing this many suffixes"
+        )]
+        max_results: Option<usize>,
     },
     Unlz {
         compressed: StdioOrPath,
@@ -246,6 +364,70 @@ enum Commands {
     DumpJsonSchema { path: PathBuf },
 }
 
+#[derive(Subcommand)]
+enum NamesCommands {
+    #[clap(about = "Brute-force the strings behind unknown names")]
+    Crack {
+        bigfile: Option<PathBuf>,
+        #[arg(long)]
+        name_type: Option<NameType>,
+        #[arg(
+            short,
+            long = "target",
+            help = "Hash to crack in addition to the unknown names of the BigFile"
+        )]
+        targets: Vec<String>,
+        #[arg(short, long = "dictionary", help = "File with one candidate per line")]
+        dictionaries: Vec<PathBuf>,
+        #[arg(
+            short,
+            long = "mask",
+            help = "Candidate pattern such as [A-Z0-9_]{1,8}"
+        )]
+        masks: Vec<String>,
+        #[arg(long = "prefix")]
+        prefixes: Vec<String>,
+        #[arg(long = "suffix")]
+        suffixes: Vec<String>,
+        #[arg(
+            long,
+            default_value_t,
+            help = "Also try this many of the most common prefixes and suffixes of known names"
+        )]
+        learn_affixes: usize,
+        #[arg(
+            long,
+            help = "Try every name style instead of only the one used by the name type"
+        )]
+        all_styles: bool,
+        #[arg(long)]
+        in_names: Vec<PathBuf>,
+        #[arg(long)]
+        out_names: Option<PathBuf>,
+    },
+    #[clap(about = "Combine and dedupe name files of the same name type")]
+    Merge {
+        #[arg(required = true)]
+        in_names: Vec<PathBuf>,
+        #[arg(long)]
+        name_type: NameType,
+        #[arg(long)]
+        out_names: PathBuf,
+        #[arg(
+            long = "prefer-source",
+            help = "Name file whose strings win collisions, in priority order"
+        )]
+        prefer_sources: Vec<PathBuf>,
+        #[arg(long, help = "Follow every written name with the source of its string")]
+        annotate_sources: bool,
+        #[arg(
+            long,
+            help = "List the names that more than one of the read strings hash to"
+        )]
+        collisions: bool,
+    },
+}
+
 #[derive(Parser)]
 #[command(author, version, long_version = build::CLAP_LONG_VERSION, about, long_about = None)]
 struct Args {
@@ -263,12 +445,24 @@ fn main() -> BffCliResult<()> {
             in_names,
             export_strategy,
             rich_suffix,
+            names,
+            classes,
+            globs,
+            reachable_from,
+            png,
         } => extract::extract(
             &bigfile,
             &directory,
             &in_names,
             export_strategy,
             &rich_suffix,
+            &ExtractFilter {
+                names,
+                classes,
+                globs,
+                reachable_from,
+            },
+            png,
         ),
         Commands::Create {
             directory,
@@ -283,11 +477,36 @@ fn main() -> BffCliResult<()> {
             version_to_write.as_ref(),
             tag.as_deref(),
         ),
+        Commands::Patch {
+            bigfile,
+            directory,
+            out_bigfile,
+            in_names,
+            out_names,
+            remove,
+            block,
+        } => patch::patch(
+            &bigfile,
+            directory.as_deref(),
+            out_bigfile.as_deref(),
+            &in_names,
+            out_names.as_deref(),
+            &remove,
+            block,
+        ),
+        Commands::ExportScene {
+            bigfile,
+            directory,
+            in_names,
+            worlds,
+            world_transforms,
+        } => export_scene::export_scene(&bigfile, &directory, &in_names, &worlds, world_transforms),
         Commands::ExtractResource {
             resource,
             directory,
             in_names,
-        } => extract_resource::extract_resource(&resource, &directory, &in_names),
+            png,
+        } => extract_resource::extract_resource(&resource, &directory, &in_names, png),
         Commands::CreateResource {
             directory,
             resource,
@@ -297,32 +516,126 @@ fn main() -> BffCliResult<()> {
             bigfile,
             in_names,
             out_reference_graph,
-        } => info::info(&bigfile, &in_names, out_reference_graph.as_deref()),
+            verify_checksums,
+        } => info::info(
+            &bigfile,
+            &in_names,
+            out_reference_graph.as_deref(),
+            verify_checksums,
+        ),
         Commands::Diff {
             old_bigfile,
             new_bigfile,
             old_names,
             new_names,
+            json,
         } => diff::diff(
             &old_bigfile,
             &new_bigfile,
             old_names.as_deref(),
             new_names.as_deref(),
+            json,
+        ),
+        Commands::Names {
+            command:
+                Some(NamesCommands::Crack {
+                    bigfile,
+                    name_type,
+                    targets,
+                    dictionaries,
+                    masks,
+                    prefixes,
+                    suffixes,
+                    learn_affixes,
+                    all_styles,
+                    in_names,
+                    out_names,
+                }),
+            ..
+        } => names::crack(
+            bigfile.as_deref(),
+            name_type,
+            &targets,
+            &CrackSources {
+                dictionaries,
+                masks,
+                prefixes,
+                suffixes,
+                learn_affixes,
+            },
+            all_styles,
+            &in_names,
+            out_names.as_deref(),
+        ),
+        Commands::Names {
+            command:
+                Some(NamesCommands::Merge {
+                    in_names,
+                    name_type,
+                    out_names,
+                    prefer_sources,
+                    annotate_sources,
+                    collisions,
+                }),
+            ..
+        } => names::merge(
+            &in_names,
+            name_type,
+            &prefer_sources,
+            &out_names,
+            annotate_sources,
+            collisions,
         ),
         Commands::Names {
+            command: None,
             bigfile,
             name_type,
             wordlist,
             in_names,
             out_names,
             reference_graph,
+            harvest,
+            harvest_scripts,
+            collisions,
+            prefer_sources,
+            annotate_sources,
+            out_names_by_source,
         } => names::names(
             bigfile.as_deref(),
             name_type,
-            wordlist,
             &in_names,
+            &prefer_sources,
             out_names.as_deref(),
-            reference_graph,
+            &NamesOptions {
+                wordlist,
+                reference_graph,
+                harvest_scripts: harvest.then_some(harvest_scripts),
+                collisions,
+                annotate_sources,
+                out_names_by_source,
+            },
+        ),
+        Commands::Crc {
+            starting,
+            algorithm,
+            format,
+            reverse: Some(hash),
+            prefix,
+            charset,
+            max_length,
+            max_results,
+            ..
+        } => crc::reverse(
+            &hash,
+            &ReverseOptions {
+                prefix,
+                charset,
+                max_length,
+                max_results,
+            },
+            starting,
+            algorithm,
+            format,
         ),
         Commands::Crc {
             string,
@@ -330,6 +643,7 @@ fn main() -> BffCliResult<()> {
             algorithm,
             mode,
             format,
+            ..
         } => crc::crc(string.as_deref(), starting, algorithm, mode, format),
         Commands::Unlz {
             compressed,
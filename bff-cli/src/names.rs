@@ -1,28 +1,40 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{BufRead as _, BufReader};
 use std::path::{Path, PathBuf};
 
-use bff::names::{NameContext, NameType, get_forced_hash_string_for_type};
-use bff::petgraph;
+use bff::bigfile::lazy::LazyBigFile;
+use bff::names::crack::{Cracker, Mask, learn_affixes, unknown_names};
+use bff::names::harvest::Harvester;
+use bff::names::{
+    ALL_NAME_STYLES,
+    Name,
+    NameContext,
+    NameSource,
+    NameType,
+    get_forced_hash_string_for_type,
+};
 use bff::petgraph::visit::{VisitMap as _, Visitable as _};
+use bff::{BffError, petgraph};
 use indicatif::{ProgressBar, ProgressStyle};
 
-use crate::error::BffCliResult;
+use crate::error::{BffCliError, BffCliResult};
 use crate::shared::{
     probe_bigfile_name_context,
-    read_bigfile,
     read_bigfile_names,
     read_in_names,
+    read_lazy_bigfile,
+    write_annotated_names,
     write_names,
+    write_names_by_source,
 };
 
-pub fn names(
+fn read_name_context(
     bigfile_path: Option<&Path>,
     name_type: Option<NameType>,
-    wordlist: bool,
     in_names: &[PathBuf],
-    out_names: Option<&Path>,
-    reference_graph: bool,
-) -> BffCliResult<()> {
+    prefer_sources: &[PathBuf],
+) -> BffCliResult<NameContext> {
     let mut name_context = if let Some(bigfile_path) = bigfile_path {
         probe_bigfile_name_context(bigfile_path)?
     } else {
@@ -34,12 +46,51 @@ pub fn names(
         read_bigfile_names(bigfile_path, &mut name_context)?;
     }
     read_in_names(in_names, &mut name_context)?;
+    name_context.prefer_sources(prefer_sources);
+    Ok(name_context)
+}
 
-    if let Some(bigfile_path) = bigfile_path {
-        let bigfile = read_bigfile(bigfile_path, &name_context)?;
+pub struct NamesOptions {
+    pub wordlist: bool,
+    pub reference_graph: bool,
+    pub harvest_scripts: Option<Vec<PathBuf>>,
+    pub collisions: bool,
+    pub annotate_sources: bool,
+    pub out_names_by_source: Option<PathBuf>,
+}
+
+// List every name that more than one string hashes to, preferred string first
+fn print_collisions(name_context: &NameContext) {
+    let mut collisions: Vec<_> = name_context.collisions().collect();
+    collisions.sort_unstable_by_key(|(name, _)| name.as_raw());
+    for (name, entries) in &collisions {
+        println!("{}", name_context.name_type().value_string_from_name(*name));
+        for entry in entries {
+            println!("    \"{}\" from {}", entry.string, entry.source);
+        }
+    }
+    eprintln!("{} collisions", collisions.len());
+}
 
-        if wordlist {
-            if reference_graph {
+pub fn names(
+    bigfile_path: Option<&Path>,
+    name_type: Option<NameType>,
+    in_names: &[PathBuf],
+    prefer_sources: &[PathBuf],
+    out_names: Option<&Path>,
+    options: &NamesOptions,
+) -> BffCliResult<()> {
+    let mut name_context = read_name_context(bigfile_path, name_type, in_names, prefer_sources)?;
+
+    let resource_names = if let Some(bigfile_path) = bigfile_path {
+        let bigfile = read_lazy_bigfile(bigfile_path, &name_context)?;
+
+        if let Some(scripts) = &options.harvest_scripts {
+            harvest(&bigfile, scripts, &mut name_context)?;
+        }
+
+        if options.wordlist {
+            if options.reference_graph {
                 let progress_bar = ProgressBar::new_spinner();
                 progress_bar.set_message("Generating reference graph");
                 let graph = bigfile.reference_graph(&name_context);
@@ -79,11 +130,8 @@ pub fn names(
                         let string = name
                             .with_context(&name_context)
                             .get_wordlist_encoded_string();
-                        let class = if let Some(bff_resource) = bigfile.bff_resource(name) {
-                            format!(
-                                ".{}",
-                                bff_resource.resource.class_name.with_context(&name_context)
-                            )
+                        let class = if let Some(class_name) = bigfile.class_name(name) {
+                            format!(".{}", class_name.with_context(&name_context))
                         } else {
                             "".to_owned()
                         };
@@ -113,11 +161,10 @@ pub fn names(
                     }
                 }
             } else {
-                for bff_resource in bigfile.bff_resources() {
-                    let name = bff_resource.resource.name;
-                    let class = bff_resource
-                        .resource
-                        .class_name
+                for name in bigfile.resource_names() {
+                    let class = bigfile
+                        .class_name(name)
+                        .unwrap_or_default()
                         .with_context(&name_context)
                         .to_string();
                     if !name_context.contains(name) {
@@ -134,12 +181,225 @@ pub fn names(
             }
         }
 
-        if let Some(out_names) = out_names {
-            let resource_names: Vec<_> = bigfile.resource_names().collect();
-            write_names(out_names, Some(resource_names.as_slice()), &name_context)?;
+        Some(bigfile.resource_names().collect::<Vec<_>>())
+    } else {
+        None
+    };
+
+    if options.collisions {
+        print_collisions(&name_context);
+    }
+
+    write_names_output(out_names, resource_names.as_deref(), &name_context, options)?;
+
+    Ok(())
+}
+
+fn write_names_output(
+    out_names: Option<&Path>,
+    names: Option<&[Name]>,
+    name_context: &NameContext,
+    options: &NamesOptions,
+) -> BffCliResult<()> {
+    if let Some(out_names) = out_names {
+        if options.annotate_sources {
+            write_annotated_names(out_names, names, name_context)?;
+        } else {
+            write_names(out_names, names, name_context)?;
+        }
+    }
+    if let Some(directory) = &options.out_names_by_source {
+        write_names_by_source(directory, names, name_context)?;
+    }
+    Ok(())
+}
+
+// Combine name files of the same name type into one, keeping the preferred string for each name
+pub fn merge(
+    in_names: &[PathBuf],
+    name_type: NameType,
+    prefer_sources: &[PathBuf],
+    out_names: &Path,
+    annotate_sources: bool,
+    collisions: bool,
+) -> BffCliResult<()> {
+    let mut name_context = NameContext::empty(name_type);
+    read_in_names(in_names, &mut name_context)?;
+    name_context.prefer_sources(prefer_sources);
+
+    if collisions {
+        print_collisions(&name_context);
+    }
+
+    if annotate_sources {
+        write_annotated_names(out_names, None, &name_context)
+    } else {
+        write_names(out_names, None, &name_context)
+    }
+}
+
+fn harvest_script_path(harvester: &mut Harvester, path: &Path) -> BffCliResult<()> {
+    if path.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            harvest_script_path(harvester, &entry?.path())?;
+        }
+    } else {
+        harvester.harvest_str(&String::from_utf8_lossy(&std::fs::read(path)?));
+    }
+    Ok(())
+}
+
+// Hash the strings in every resource, the manifest, and the given script files or directories to
+// resolve names in the reference graph
+fn harvest(
+    bigfile: &LazyBigFile<bff::BufReader<File>>,
+    scripts: &[PathBuf],
+    name_context: &mut NameContext,
+) -> BffCliResult<()> {
+    let progress_bar = ProgressBar::new_spinner();
+    progress_bar.set_message("Generating reference graph");
+    let graph = bigfile.reference_graph(name_context);
+    let targets = unknown_names(name_context, graph.node_weights().copied());
+    let mut harvester = Harvester::new(name_context.name_type(), targets);
+
+    progress_bar.set_message("Harvesting names");
+    progress_bar.set_style(ProgressStyle::default_bar());
+    progress_bar.set_length(bigfile.resource_names().len() as u64);
+    harvester.harvest_serialize(bigfile.manifest(), name_context)?;
+    for name in bigfile.resource_names() {
+        progress_bar.inc(1);
+        let Some(resource) = bigfile.resource(name, name_context)? else {
+            continue;
+        };
+        if let Ok(bff_class) = bigfile.bff_resource(&resource).bff_class(name_context) {
+            harvester.harvest_serialize(&bff_class.class, name_context)?;
+        }
+    }
+
+    for script in scripts {
+        harvest_script_path(&mut harvester, script)?;
+    }
+    progress_bar.finish_and_clear();
+
+    for string in harvester.into_found().into_values() {
+        name_context.insert_source(&string, &NameSource::Harvested);
+    }
+
+    Ok(())
+}
+
+pub struct CrackSources {
+    pub dictionaries: Vec<PathBuf>,
+    pub masks: Vec<String>,
+    pub prefixes: Vec<String>,
+    pub suffixes: Vec<String>,
+    pub learn_affixes: usize,
+}
+
+pub fn crack(
+    bigfile_path: Option<&Path>,
+    name_type: Option<NameType>,
+    targets: &[String],
+    sources: &CrackSources,
+    all_styles: bool,
+    in_names: &[PathBuf],
+    out_names: Option<&Path>,
+) -> BffCliResult<()> {
+    let mut name_context = read_name_context(bigfile_path, name_type, in_names, &[])?;
+    let name_type = name_context.name_type();
+    let masks = sources
+        .masks
+        .iter()
+        .map(|mask| mask.parse::<Mask>().map_err(BffError::from))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let progress_bar = ProgressBar::new_spinner();
+    let mut target_names = HashSet::new();
+    if let Some(bigfile_path) = bigfile_path {
+        progress_bar.set_message("Finding unknown names");
+        let bigfile = read_lazy_bigfile(bigfile_path, &name_context)?;
+        let graph = bigfile.reference_graph(&name_context);
+        target_names.extend(unknown_names(&name_context, graph.node_weights().copied()));
+    }
+    for target in targets {
+        let name =
+            name_type
+                .parse_name_value(target)
+                .ok_or_else(|| BffCliError::InvalidNameValue {
+                    value: target.clone(),
+                })?;
+        target_names.extend(unknown_names(&name_context, [name]));
+    }
+
+    let mut cracker = Cracker::new(name_type, target_names);
+    cracker.add_prefixes(sources.prefixes.iter().cloned());
+    cracker.add_suffixes(sources.suffixes.iter().cloned());
+    if sources.learn_affixes > 0 {
+        let (prefixes, suffixes) = learn_affixes(name_context.strings(), sources.learn_affixes);
+        cracker.add_prefixes(prefixes);
+        cracker.add_suffixes(suffixes);
+    }
+    if all_styles {
+        cracker.set_styles(ALL_NAME_STYLES.to_vec());
+    }
+
+    let mut cracked: HashMap<Name, Vec<String>> = HashMap::new();
+    let mut merge_cracked = |found: HashMap<Name, Vec<String>>| {
+        for (name, strings) in found {
+            cracked.entry(name).or_default().extend(strings);
         }
-    } else if let Some(out_names) = out_names {
-        write_names(out_names, None, &name_context)?;
+    };
+    for dictionary in &sources.dictionaries {
+        progress_bar.set_message(format!(
+            "Cracking {} names with {}",
+            cracker.targets().len(),
+            dictionary.display()
+        ));
+        let words = BufReader::new(File::open(dictionary)?)
+            .lines()
+            .collect::<Result<Vec<_>, _>>()?;
+        merge_cracked(cracker.crack_words(&words));
+    }
+    for (mask, pattern) in masks.iter().zip(&sources.masks) {
+        progress_bar.set_message(format!(
+            "Cracking {} names with {} candidates of {}",
+            cracker.targets().len(),
+            mask.candidate_count(),
+            pattern
+        ));
+        merge_cracked(cracker.crack_mask(mask));
+    }
+    progress_bar.finish_and_clear();
+
+    // Every match is kept, names with more than one show up as collisions
+    let collision_count = cracked
+        .values_mut()
+        .filter(|strings| {
+            strings.sort_unstable();
+            strings.dedup();
+            strings.len() > 1
+        })
+        .count();
+    let mut cracked: Vec<(Name, String)> = cracked
+        .into_iter()
+        .flat_map(|(name, strings)| strings.into_iter().map(move |string| (name, string)))
+        .collect();
+    cracked.sort_unstable_by(|(_, a), (_, b)| a.cmp(b));
+    if collision_count > 0 {
+        eprintln!("{collision_count} names cracked to more than one string");
+    }
+    for (name, string) in &cracked {
+        println!(
+            r#"{} "{}""#,
+            name_type.value_string_from_name(*name),
+            string
+        );
+        name_context.insert_source(string, &NameSource::Cracked);
+    }
+
+    if let Some(out_names) = out_names {
+        let cracked_names: Vec<_> = cracked.iter().map(|(name, _)| *name).collect();
+        write_names(out_names, Some(cracked_names.as_slice()), &name_context)?;
     }
 
     Ok(())
@@ -0,0 +1,107 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use bff::bigfile::BigFile;
+use bff::names::NameContext;
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::error::{BffCliError, BffCliResult};
+use crate::shared::{
+    probe_bigfile_name_context,
+    read_bigfile,
+    read_bigfile_names,
+    read_in_names,
+    read_resource_path,
+    write_names,
+};
+
+pub fn patch(
+    bigfile_path: &Path,
+    directory: Option<&Path>,
+    out_bigfile: Option<&Path>,
+    in_names: &[PathBuf],
+    out_names: Option<&Path>,
+    remove: &[String],
+    block: Option<usize>,
+) -> BffCliResult<()> {
+    let mut name_context = probe_bigfile_name_context(bigfile_path)?;
+    let progress_bar = ProgressBar::new_spinner();
+    progress_bar.set_message("Reading names");
+    read_bigfile_names(bigfile_path, &mut name_context)?;
+    read_in_names(in_names, &mut name_context)?;
+
+    progress_bar.set_message("Reading BigFile");
+    let mut bigfile = read_bigfile(bigfile_path, &name_context)?;
+
+    if !remove.is_empty() {
+        progress_bar.set_message("Removing resources");
+        let removed: Vec<_> = bigfile
+            .resource_names()
+            .filter(|name| remove.contains(&name.with_context(&name_context).to_string()))
+            .collect();
+        for name in removed {
+            bigfile.remove_resource(name);
+        }
+    }
+
+    if let Some(directory) = directory {
+        progress_bar.set_style(ProgressStyle::default_bar());
+        progress_bar.set_length(std::fs::read_dir(directory)?.count() as u64);
+
+        let mut patched = HashSet::new();
+        for file in std::fs::read_dir(directory)? {
+            let path = file?.path();
+            progress_bar.inc(1);
+            if let Some(resource) = read_resource_path(&path, &mut name_context)? {
+                if !patched.insert(resource.name) {
+                    return Err(BffCliError::DuplicateResource {
+                        name: resource.name,
+                    });
+                }
+                bigfile.insert_resource(resource, block)?;
+            }
+        }
+    }
+
+    progress_bar.set_style(ProgressStyle::default_spinner());
+    progress_bar.set_message("Writing BigFile");
+
+    match out_bigfile {
+        Some(out_bigfile) => write_bigfile(out_bigfile, &bigfile, &name_context)?,
+        None => {
+            // Write next to the input and rename it over the input once the
+            // write has succeeded so a failure never loses the original
+            let mut temp_file_name = bigfile_path.file_name().unwrap_or_default().to_owned();
+            temp_file_name.push(".tmp");
+            let temp_path = bigfile_path.with_file_name(temp_file_name);
+            let result = write_bigfile(&temp_path, &bigfile, &name_context)
+                .and_then(|()| Ok(std::fs::rename(&temp_path, bigfile_path)?));
+            if result.is_err() {
+                let _ = std::fs::remove_file(&temp_path);
+            }
+            result?;
+        }
+    }
+
+    if let Some(out_names) = out_names {
+        progress_bar.set_message("Writing names");
+        let resource_names: Vec<_> = bigfile.resource_names().collect();
+        write_names(out_names, Some(resource_names.as_slice()), &name_context)?;
+    }
+
+    progress_bar.finish_and_clear();
+
+    Ok(())
+}
+
+fn write_bigfile(path: &Path, bigfile: &BigFile, name_context: &NameContext) -> BffCliResult<()> {
+    let mut bigfile_writer = BufWriter::new(File::create(path)?);
+    bigfile.write(&mut bigfile_writer, None, None, name_context)?;
+    bigfile_writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    Ok(())
+}
@@ -1,14 +1,20 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
 use std::fs::File;
 use std::io::BufWriter;
 use std::path::{Path, PathBuf};
 
-use bff::BufReader;
 use bff::bigfile::BigFile;
+use bff::bigfile::lazy::LazyBigFile;
 use bff::bigfile::platforms::{Platform, try_platform_style_to_name_extension};
-use bff::names::{Name, NameContext};
-use bff::traits::Artifact;
+use bff::bigfile::resource::Resource;
+use bff::bigfile::resource::bff_resource::BffResource;
+use bff::class::Class;
+use bff::class::bff_class::BffClass;
+use bff::helpers::texture::dds_to_png;
+use bff::names::{Name, NameContext, NameSource};
+use bff::traits::{Artifact, Import as _};
+use bff::{BffError, BufReader};
 
 use crate::error::{BffCliError, BffCliResult};
 
@@ -24,9 +30,9 @@ pub fn read_bigfile_names(bigfile_path: &Path, name_context: &mut NameContext) -
             try_platform_style_to_name_extension(extension.try_into()?, extension.try_into()?)?;
         let in_name = bigfile_path.with_extension(name_extension);
 
-        if let Ok(f) = File::open(in_name) {
+        if let Ok(f) = File::open(&in_name) {
             let mut reader = BufReader::new(f);
-            name_context.read(&mut reader)?;
+            name_context.read_source(&mut reader, &NameSource::file(&in_name))?;
         }
     }
 
@@ -37,7 +43,7 @@ pub fn read_in_names(in_names: &[PathBuf], name_context: &mut NameContext) -> Bf
     for in_name in in_names {
         let f = File::open(in_name)?;
         let mut reader = BufReader::new(f);
-        name_context.read(&mut reader)?;
+        name_context.read_source(&mut reader, &NameSource::file(in_name))?;
     }
 
     Ok(())
@@ -55,6 +61,58 @@ pub fn write_names(
     Ok(())
 }
 
+pub fn write_annotated_names(
+    out_names: &Path,
+    names: Option<&[Name]>,
+    name_context: &NameContext,
+) -> BffCliResult<()> {
+    let f = File::create(out_names)?;
+    let mut writer = BufWriter::new(f);
+    name_context.write_annotated(&mut writer, names)?;
+
+    Ok(())
+}
+
+// One name file per source in the directory. Name files keep their file name and the other sources
+// are named after themselves. Builtin names are not written.
+pub fn write_names_by_source(
+    directory: &Path,
+    names: Option<&[Name]>,
+    name_context: &NameContext,
+) -> BffCliResult<()> {
+    std::fs::create_dir_all(directory)?;
+
+    let mut sources: Vec<_> = name_context
+        .sources()
+        .into_iter()
+        .filter(|source| **source != NameSource::Builtin)
+        .collect();
+    sources.sort_unstable_by_key(|source| source.to_string());
+
+    let mut file_names = HashSet::new();
+    for source in sources {
+        let file_name = match source {
+            NameSource::File(path) => Path::new(&**path)
+                .file_name()
+                .map_or_else(|| OsString::from("names"), OsString::from),
+            _ => OsString::from(format!("{source}.txt")),
+        };
+        let mut unique_file_name = file_name.clone();
+        let mut i = 1;
+        while !file_names.insert(unique_file_name.clone()) {
+            unique_file_name = OsString::from(format!("{i}_"));
+            unique_file_name.push(&file_name);
+            i += 1;
+        }
+
+        let f = File::create(directory.join(unique_file_name))?;
+        let mut writer = BufWriter::new(f);
+        name_context.write_source(&mut writer, names, source)?;
+    }
+
+    Ok(())
+}
+
 pub fn resolve_platform(bigfile_path: &Path) -> BffCliResult<Platform> {
     let extension =
         bigfile_path
@@ -72,6 +130,16 @@ pub fn read_bigfile(bigfile_path: &Path, name_context: &NameContext) -> BffCliRe
     Ok(BigFile::read_platform(&mut reader, platform, name_context)?)
 }
 
+pub fn read_lazy_bigfile(
+    bigfile_path: &Path,
+    name_context: &NameContext,
+) -> BffCliResult<LazyBigFile<BufReader<File>>> {
+    let platform = resolve_platform(bigfile_path)?;
+    let f = File::open(bigfile_path)?;
+    let reader = BufReader::new(f);
+    Ok(LazyBigFile::read_platform(reader, platform, name_context)?)
+}
+
 pub fn probe_bigfile_name_context(bigfile_path: &Path) -> BffCliResult<NameContext> {
     let f = File::open(bigfile_path)?;
     let mut reader = BufReader::new(f);
@@ -103,16 +171,81 @@ pub fn read_artifacts(directory: &Path) -> BffCliResult<HashMap<OsString, Artifa
         let artifact = match extension {
             "bin" => Artifact::Binary(std::fs::read(&path)?),
             "dds" => Artifact::Dds(std::fs::read(&path)?),
+            "png" => Artifact::Png(std::fs::read(&path)?),
             "wav" => Artifact::Wav(std::fs::read(&path)?),
             "txt" => Artifact::Text(std::fs::read_to_string(&path)?),
+            "gltf" => Artifact::Gltf(std::fs::read_to_string(&path)?),
+            "geojson" => Artifact::GeoJson(std::fs::read_to_string(&path)?),
+            "svg" => Artifact::Svg(std::fs::read_to_string(&path)?),
+            "obj" => Artifact::Obj(std::fs::read_to_string(&path)?),
+            "fnt" => Artifact::Fnt(std::fs::read_to_string(&path)?),
+            "json" => Artifact::Json(std::fs::read_to_string(&path)?),
             _ => continue,
         };
-        artifacts.insert(artifact_name, artifact);
+        // Artifacts are keyed by stem, so data.dds next to data.png would silently shadow one
+        if artifacts.insert(artifact_name, artifact).is_some() {
+            return Err(BffCliError::DuplicateArtifact { path });
+        }
     }
 
     Ok(artifacts)
 }
 
+// Classes without artifacts or without an importer keep their deserialized state
+pub fn import_artifacts(
+    class: &mut Class,
+    artifacts: &HashMap<OsString, Artifact>,
+    name_context: &mut NameContext,
+) -> BffCliResult<()> {
+    if artifacts.is_empty() {
+        return Ok(());
+    }
+
+    match name_context.scope_mut(|| class.import(artifacts)) {
+        Ok(()) | Err(BffError::UnimplementedImportExport) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// Reads either a BFF resource file or a rich resource directory
+pub fn read_resource_path(
+    path: &Path,
+    name_context: &mut NameContext,
+) -> BffCliResult<Option<Resource>> {
+    if path.is_file() {
+        let mut file_reader = BufReader::new(File::open(path)?);
+        let BffResource { resource, .. } = BffResource::read(&mut file_reader, name_context)?;
+        Ok(Some(resource))
+    } else if path.is_dir() {
+        let resource_serialized_reader = BufReader::new(File::open(resource_json_path(path))?);
+        let mut bff_class: BffClass =
+            bff::names::json::from_reader(resource_serialized_reader, name_context)?;
+
+        let artifacts = read_artifacts(path)?;
+
+        import_artifacts(&mut bff_class.class, &artifacts, name_context)?;
+
+        let BffResource { resource, .. } = bff_class.bff_resource(name_context)?;
+        Ok(Some(resource))
+    } else {
+        Ok(None)
+    }
+}
+
+// DDS artifacts that can't be decoded are left alone
+pub fn dds_artifacts_to_png(artifacts: HashMap<OsString, Artifact>) -> HashMap<OsString, Artifact> {
+    artifacts
+        .into_iter()
+        .map(|(name, artifact)| match artifact {
+            Artifact::Dds(dds) => match dds_to_png(&dds) {
+                Ok(png) => (name, Artifact::Png(png)),
+                Err(_) => (name, Artifact::Dds(dds)),
+            },
+            artifact => (name, artifact),
+        })
+        .collect()
+}
+
 pub fn write_artifacts(
     directory: &Path,
     artifacts: impl IntoIterator<Item = (OsString, Artifact)>,
@@ -122,8 +255,17 @@ pub fn write_artifacts(
         match artifact {
             Artifact::Binary(bytes) => std::fs::write(artifact_path.with_extension("bin"), bytes)?,
             Artifact::Dds(bytes) => std::fs::write(artifact_path.with_extension("dds"), bytes)?,
+            Artifact::Png(bytes) => std::fs::write(artifact_path.with_extension("png"), bytes)?,
             Artifact::Wav(bytes) => std::fs::write(artifact_path.with_extension("wav"), bytes)?,
             Artifact::Text(text) => std::fs::write(artifact_path.with_extension("txt"), text)?,
+            Artifact::Gltf(text) => std::fs::write(artifact_path.with_extension("gltf"), text)?,
+            Artifact::GeoJson(text) => {
+                std::fs::write(artifact_path.with_extension("geojson"), text)?
+            }
+            Artifact::Svg(text) => std::fs::write(artifact_path.with_extension("svg"), text)?,
+            Artifact::Obj(text) => std::fs::write(artifact_path.with_extension("obj"), text)?,
+            Artifact::Fnt(text) => std::fs::write(artifact_path.with_extension("fnt"), text)?,
+            Artifact::Json(text) => std::fs::write(artifact_path.with_extension("json"), text)?,
         }
     }
 
@@ -0,0 +1,58 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use binrw::BinResult;
+
+use crate::crc::asobo_alternate32;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BlockChecksumMismatch {
+    pub block_index: usize,
+    pub stored: i32,
+    pub calculated: i32,
+}
+
+pub struct BlockChecksumDescription {
+    pub data_size: u32,
+    pub padded_size: u32,
+    pub checksum: Option<i32>,
+}
+
+// Checksum stored in the description of a written block. Checksums are always
+// written so edited blocks pass the engine's integrity check.
+pub fn block_checksum(block_data: &[u8]) -> Option<i32> {
+    Some(asobo_alternate32(block_data))
+}
+
+// Checksums are calculated using Asobo Alternate on the unpadded block. Blocks
+// without a stored checksum are skipped. The reader must be positioned at the
+// start of the first block.
+pub fn verify_block_checksums<R: Read + Seek>(
+    reader: &mut R,
+    blocks: impl IntoIterator<Item = BlockChecksumDescription>,
+) -> BinResult<Vec<BlockChecksumMismatch>> {
+    let mut mismatches = Vec::new();
+
+    for (block_index, block) in blocks.into_iter().enumerate() {
+        let Some(stored) = block.checksum else {
+            reader.seek(SeekFrom::Current(block.padded_size as i64))?;
+            continue;
+        };
+
+        let mut data = vec![0; block.data_size as usize];
+        reader.read_exact(&mut data)?;
+        reader.seek(SeekFrom::Current(
+            block.padded_size as i64 - block.data_size as i64,
+        ))?;
+
+        let calculated = asobo_alternate32(&data);
+        if calculated != stored {
+            mismatches.push(BlockChecksumMismatch {
+                block_index,
+                stored,
+                calculated,
+            });
+        }
+    }
+
+    Ok(mismatches)
+}
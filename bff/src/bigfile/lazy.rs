@@ -0,0 +1,139 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::Mutex;
+
+use petgraph::Graph;
+
+use crate::BffResult;
+use crate::bigfile::manifest::Manifest;
+use crate::bigfile::resource::Resource;
+use crate::bigfile::resource::bff_resource::BffResourceRef;
+use crate::bigfile::{BigFile, add_references};
+use crate::names::{Name, NameContext};
+use crate::traits::ReferencedNames as _;
+
+pub enum ResourceLocation {
+    // Offset of the resource record, decoded with the backend's resource reader
+    Offset(u64),
+    // Backends that cannot locate resources without decoding them keep them in memory
+    Loaded(Resource),
+}
+
+pub struct IndexedResource {
+    pub class_name: Name,
+    pub location: ResourceLocation,
+}
+
+pub struct BigFileIndex {
+    pub manifest: Manifest,
+    pub resources: HashMap<Name, IndexedResource>,
+}
+
+impl From<BigFile> for BigFileIndex {
+    fn from(bigfile: BigFile) -> Self {
+        Self {
+            manifest: bigfile.manifest,
+            resources: bigfile
+                .resources
+                .into_iter()
+                .map(|(name, resource)| {
+                    (
+                        name,
+                        IndexedResource {
+                            class_name: resource.class_name,
+                            location: ResourceLocation::Loaded(resource),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+// A BigFile that only reads the manifest and an index of resource offsets up front. Resources are
+// decoded on demand. Versions without an index-only reader, which includes every v1 version, are
+// fully decoded when the index is read.
+pub struct LazyBigFile<R> {
+    reader: Mutex<R>,
+    manifest: Manifest,
+    resources: HashMap<Name, IndexedResource>,
+}
+
+impl<R: Read + Seek> LazyBigFile<R> {
+    pub fn new(reader: R, index: BigFileIndex) -> Self {
+        Self {
+            reader: Mutex::new(reader),
+            manifest: index.manifest,
+            resources: index.resources,
+        }
+    }
+
+    pub const fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    pub fn resource_names(&self) -> impl ExactSizeIterator<Item = Name> + '_ {
+        self.resources.keys().copied()
+    }
+
+    pub fn contains(&self, name: Name) -> bool {
+        self.resources.contains_key(&name)
+    }
+
+    pub fn class_name(&self, name: Name) -> Option<Name> {
+        self.resources
+            .get(&name)
+            .map(|indexed_resource| indexed_resource.class_name)
+    }
+
+    pub fn resource(
+        &self,
+        name: Name,
+        name_context: &NameContext,
+    ) -> BffResult<Option<Cow<'_, Resource>>> {
+        let Some(indexed_resource) = self.resources.get(&name) else {
+            return Ok(None);
+        };
+
+        match &indexed_resource.location {
+            ResourceLocation::Offset(offset) => {
+                let mut reader = self.reader.lock().unwrap();
+                reader.seek(SeekFrom::Start(*offset))?;
+                let resource = Resource::read_resource(
+                    &mut *reader,
+                    self.manifest.platform,
+                    &self.manifest.version,
+                    name_context,
+                )?;
+                Ok(Some(Cow::Owned(resource)))
+            }
+            ResourceLocation::Loaded(resource) => Ok(Some(Cow::Borrowed(resource))),
+        }
+    }
+
+    pub const fn bff_resource<'a>(&'a self, resource: &'a Resource) -> BffResourceRef<'a> {
+        BffResourceRef {
+            platform: self.manifest.platform,
+            version: &self.manifest.version,
+            resource,
+        }
+    }
+
+    // Resources are decoded one at a time so the whole BigFile is never in memory
+    pub fn reference_graph(&self, name_context: &NameContext) -> Graph<Name, ()> {
+        let mut graph = Graph::with_capacity(self.resources.len(), 0);
+        let mut node_ids = HashMap::new();
+        for name in self.resource_names() {
+            let references = self
+                .resource(name, name_context)
+                .ok()
+                .flatten()
+                .and_then(|resource| self.bff_resource(&resource).bff_class(name_context).ok())
+                .map(|bff_class| bff_class.class.referenced_names())
+                .unwrap_or_default();
+            add_references(&mut graph, &mut node_ids, name, references);
+        }
+        graph
+    }
+}
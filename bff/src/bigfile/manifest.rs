@@ -67,3 +67,97 @@ pub struct Manifest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pool: Option<ManifestPool>,
 }
+
+impl Manifest {
+    /// A copy of the manifest with only the resources for which `keep` returns true. Blocks keep
+    /// their place even when they end up empty and the pool indices are renumbered to match.
+    pub fn filtered(&self, keep: impl Fn(Name) -> bool) -> Self {
+        let blocks = self
+            .blocks
+            .iter()
+            .map(|block| ManifestBlock {
+                offset: block.offset,
+                checksum: block.checksum,
+                compress: block.compress,
+                resources: block
+                    .resources
+                    .iter()
+                    .filter(|resource| keep(resource.name))
+                    .map(|resource| ManifestResource {
+                        name: resource.name,
+                        compress: resource.compress,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            version: self.version.clone(),
+            platform: self.platform,
+            version_xple: self.version_xple,
+            bigfile_type: self.bigfile_type,
+            pool_manifest_unused: self.pool_manifest_unused,
+            incredi_builder_string: self.incredi_builder_string.clone(),
+            blocks,
+            pool: self.pool.as_ref().map(|pool| pool.filtered(keep)),
+        }
+    }
+}
+
+impl ManifestPool {
+    fn filtered(&self, keep: impl Fn(Name) -> bool) -> Self {
+        let mut new_indices = Vec::with_capacity(self.resource_entries.len());
+        let mut resource_entries = Vec::new();
+        for entry in &self.resource_entries {
+            if keep(entry.name) {
+                new_indices.push(Some(resource_entries.len() as u32));
+                resource_entries.push(ManifestPoolResourceEntry {
+                    name: entry.name,
+                    reference_record_index: entry.reference_record_index,
+                });
+            } else {
+                new_indices.push(None);
+            }
+        }
+
+        let new_index = |index: &u32| new_indices.get(*index as usize).copied().flatten();
+        let resource_entry_indices = self
+            .resource_entry_indices
+            .iter()
+            .filter_map(new_index)
+            .collect();
+
+        // Each record now starts at the number of kept indices before its old start
+        let reference_records = self
+            .reference_records
+            .iter()
+            .map(|record| {
+                let start = record.resource_entries_starting_index as usize;
+                let end = start + record.resource_entries_count as usize;
+                let kept_before = self
+                    .resource_entry_indices
+                    .iter()
+                    .take(start)
+                    .filter(|index| new_index(*index).is_some())
+                    .count();
+                let kept = self
+                    .resource_entry_indices
+                    .iter()
+                    .take(end)
+                    .skip(start)
+                    .filter(|index| new_index(*index).is_some())
+                    .count();
+                ManifestPoolReferenceRecord {
+                    resource_entries_starting_index: kept_before as u32,
+                    resource_entries_count: kept as u16,
+                }
+            })
+            .collect();
+
+        Self {
+            resource_entry_indices,
+            resource_entries,
+            reference_records,
+        }
+    }
+}
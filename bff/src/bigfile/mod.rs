@@ -1,4 +1,7 @@
+pub mod checksum;
+pub mod lazy;
 pub mod manifest;
+mod patch;
 pub mod platforms;
 pub mod resource;
 mod v1_06_63_02_pc;
@@ -17,6 +20,7 @@ pub mod versions;
 use std::collections::HashMap;
 
 use petgraph::Graph;
+use petgraph::graph::NodeIndex;
 
 use crate::bigfile::manifest::Manifest;
 use crate::bigfile::resource::Resource;
@@ -95,13 +99,7 @@ impl BigFile {
                 .bff_class(name_context)
                 .map(|bff_class| bff_class.class.referenced_names())
                 .unwrap_or_default();
-            let from_id = *node_ids.entry(name).or_insert_with(|| graph.add_node(name));
-            for reference in references {
-                let to_id = *node_ids
-                    .entry(reference)
-                    .or_insert_with(|| graph.add_node(reference));
-                graph.add_edge(from_id, to_id, ());
-            }
+            add_references(&mut graph, &mut node_ids, name, references);
         }
         graph
     }
@@ -122,6 +120,21 @@ impl BigFile {
     }
 }
 
+fn add_references<I: IntoIterator<Item = Name>>(
+    graph: &mut Graph<Name, ()>,
+    node_ids: &mut HashMap<Name, NodeIndex>,
+    name: Name,
+    references: I,
+) {
+    let from_id = *node_ids.entry(name).or_insert_with(|| graph.add_node(name));
+    for reference in references {
+        let to_id = *node_ids
+            .entry(reference)
+            .or_insert_with(|| graph.add_node(reference));
+        graph.add_edge(from_id, to_id, ());
+    }
+}
+
 // TODO: All this type stuff is nonsense. Just have modules and re-check the bf
 // version/platform there instead of using generics and binrw derive everywhere.
 // Actually, I like the idea of having a monolithic backend and a
@@ -0,0 +1,164 @@
+use crate::BffResult;
+use crate::bigfile::BigFile;
+use crate::bigfile::manifest::{
+    ManifestBlock,
+    ManifestPool,
+    ManifestPoolResourceEntry,
+    ManifestResource,
+};
+use crate::bigfile::resource::{Resource, ResourceData};
+use crate::error::InvalidBlockIndexError;
+use crate::names::Name;
+
+impl BigFile {
+    pub fn resource(&self, name: Name) -> Option<&Resource> {
+        self.resources.get(&name)
+    }
+
+    pub fn contains(&self, name: Name) -> bool {
+        self.resources.contains_key(&name)
+    }
+
+    // Replaces the resource in place if a resource with the same name already
+    // exists, otherwise appends it to the end of `block_index` or the last
+    // block. A new resource is pooled alongside the other pooled resources of
+    // its block, and stays in the block if the block has none. Block checksums
+    // and pool sizes are recalculated by the writer.
+    pub fn insert_resource(
+        &mut self,
+        resource: Resource,
+        block_index: Option<usize>,
+    ) -> BffResult<Option<Resource>> {
+        let name = resource.name;
+        let splittable = matches!(resource.data, ResourceData::SplitData { .. });
+
+        if let Some(old) = self.resources.get_mut(&name) {
+            // Only the body of split resources can live in the pool
+            if !splittable && let Some(pool) = &mut self.manifest.pool {
+                remove_pool_entry(pool, name);
+            }
+            return Ok(Some(std::mem::replace(old, resource)));
+        }
+
+        let block_count = self.manifest.blocks.len();
+        let block_index = block_index.unwrap_or(block_count.saturating_sub(1));
+        let block = self
+            .manifest
+            .blocks
+            .get_mut(block_index)
+            .ok_or(InvalidBlockIndexError::new(block_index, block_count))?;
+
+        if splittable && let Some(pool) = &mut self.manifest.pool {
+            let record_index = block
+                .resources
+                .iter()
+                .find_map(|r| block_reference_record(pool, r.name));
+            if let Some(record_index) = record_index {
+                insert_pool_entry(pool, name, record_index);
+            }
+        }
+
+        // Follow the compression of the neighbouring resources
+        let compress = block.resources.last().and_then(|r| r.compress);
+        block.resources.push(ManifestResource { name, compress });
+
+        self.resources.insert(name, resource);
+
+        Ok(None)
+    }
+
+    pub fn remove_resource(&mut self, name: Name) -> Option<Resource> {
+        let resource = self.resources.remove(&name)?;
+
+        for block in self.manifest.blocks.iter_mut() {
+            block.resources.retain(|r| r.name != name);
+        }
+
+        if let Some(pool) = &mut self.manifest.pool {
+            remove_pool_entry(pool, name);
+        }
+
+        Some(resource)
+    }
+
+    pub fn block_of(&self, name: Name) -> Option<(usize, &ManifestBlock)> {
+        self.manifest
+            .blocks
+            .iter()
+            .enumerate()
+            .find(|(_, block)| block.resources.iter().any(|r| r.name == name))
+    }
+}
+
+// The reference record that loads the pooled body of `name`
+fn block_reference_record(pool: &ManifestPool, name: Name) -> Option<usize> {
+    let entry_index = pool.resource_entries.iter().position(|e| e.name == name)? as u32;
+    pool.reference_records.iter().position(|record| {
+        let start = record.resource_entries_starting_index as usize;
+        let end = start + record.resource_entries_count as usize;
+        pool.resource_entry_indices
+            .get(start..end)
+            .is_some_and(|indices| indices.contains(&entry_index))
+    })
+}
+
+// Add a pool entry for `name` at the end of the reference record so it is
+// loaded with the rest of the record
+fn insert_pool_entry(pool: &mut ManifestPool, name: Name, record_index: usize) {
+    let entry_index = pool.resource_entries.len() as u32;
+    pool.resource_entries.push(ManifestPoolResourceEntry {
+        name,
+        reference_record_index: record_index as u32,
+    });
+
+    let record = &mut pool.reference_records[record_index];
+    let position = record.resource_entries_starting_index + record.resource_entries_count as u32;
+    record.resource_entries_count += 1;
+    pool.resource_entry_indices
+        .insert(position as usize, entry_index);
+
+    // Records after the insertion point move along by one
+    for (i, record) in pool.reference_records.iter_mut().enumerate() {
+        if i != record_index && record.resource_entries_starting_index >= position {
+            record.resource_entries_starting_index += 1;
+        }
+    }
+}
+
+fn remove_pool_entry(pool: &mut ManifestPool, name: Name) {
+    let Some(entry_index) = pool.resource_entries.iter().position(|e| e.name == name) else {
+        return;
+    };
+    pool.resource_entries.remove(entry_index);
+    let entry_index = entry_index as u32;
+
+    // Positions in resource_entry_indices that referenced the removed entry
+    let removed_positions: Vec<u32> = pool
+        .resource_entry_indices
+        .iter()
+        .enumerate()
+        .filter(|(_, i)| **i == entry_index)
+        .map(|(position, _)| position as u32)
+        .collect();
+
+    pool.resource_entry_indices.retain(|i| *i != entry_index);
+    for i in pool.resource_entry_indices.iter_mut() {
+        if *i > entry_index {
+            *i -= 1;
+        }
+    }
+
+    // Reference records are ranges into resource_entry_indices so shrink them
+    // by the number of removed positions before and inside each range
+    for record in pool.reference_records.iter_mut() {
+        let start = record.resource_entries_starting_index;
+        let end = start + record.resource_entries_count as u32;
+        let before = removed_positions.iter().filter(|p| **p < start).count() as u32;
+        let inside = removed_positions
+            .iter()
+            .filter(|p| (start..end).contains(*p))
+            .count() as u16;
+        record.resource_entries_starting_index -= before;
+        record.resource_entries_count -= inside;
+    }
+}
@@ -2,7 +2,7 @@ use crate::names::Name;
 
 pub mod bff_resource;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub enum ResourceData {
     Data(Box<[u8]>),
     SplitData {
@@ -11,7 +11,7 @@ pub enum ResourceData {
     },
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Resource {
     pub class_name: Name,
     pub name: Name,
@@ -4,7 +4,7 @@ pub mod resource;
 
 use std::cmp::max;
 use std::collections::HashMap;
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 
 use binrw::{BinRead as _, BinResult, BinWrite as _, Endian};
 use block::Block;
@@ -13,6 +13,7 @@ use resource::Resource;
 
 use crate::BffResult;
 use crate::bigfile::BigFile;
+use crate::bigfile::checksum::{BlockChecksumMismatch, block_checksum, verify_block_checksums};
 use crate::bigfile::manifest::*;
 use crate::bigfile::platforms::Platform;
 use crate::bigfile::resource::ResourceData::SplitData;
@@ -88,6 +89,18 @@ impl BigFileIo for BigFileV1_06_63_02PC {
         })
     }
 
+    fn verify_checksums<R: Read + Seek>(
+        reader: &mut R,
+        _version: Version,
+        platform: Platform,
+    ) -> BffResult<Vec<BlockChecksumMismatch>> {
+        let header = Header::read_options(reader, platform.into(), ())?;
+        Ok(verify_block_checksums(
+            reader,
+            header.block_descriptions.iter().map(Into::into),
+        )?)
+    }
+
     fn write<W: Write + Seek>(
         bigfile: &BigFile,
         writer: &mut W,
@@ -106,7 +119,7 @@ impl BigFileIo for BigFileV1_06_63_02PC {
         let mut compressed = HashMap::new();
 
         for (i, block) in bigfile.manifest.blocks.iter().enumerate() {
-            let block_begin = writer.stream_position()?;
+            let block_writer = &mut Cursor::new(Vec::new());
 
             let mut calculated_working_buffer_offset = 0usize;
 
@@ -116,35 +129,41 @@ impl BigFileIo for BigFileV1_06_63_02PC {
                 let is_compressed = block_resource.compress.unwrap_or_default();
                 compressed.insert(block_resource.name, is_compressed);
                 let resource = bigfile.resources.get(&block_resource.name).unwrap();
-                let begin_resource = writer.stream_position()?;
+                let begin_resource = block_writer.stream_position()?;
                 match (&resource.data, is_compressed) {
                     (SplitData { link_header, body }, true) => {
-                        let begin_header = writer.stream_position()?;
-                        writer.seek(SeekFrom::Current(24))?;
-                        writer.write_all(link_header)?;
-                        let begin_body = writer.stream_position()?;
-                        lzrs_compress_data_with_header_writer_internal(body, writer, endian, ())?;
-                        let end_body = writer.stream_position()?;
-                        writer.seek(SeekFrom::Start(begin_header))?;
+                        let begin_header = block_writer.stream_position()?;
+                        block_writer.seek(SeekFrom::Current(24))?;
+                        block_writer.write_all(link_header)?;
+                        let begin_body = block_writer.stream_position()?;
+                        lzrs_compress_data_with_header_writer_internal(
+                            body,
+                            block_writer,
+                            endian,
+                            (),
+                        )?;
+                        let end_body = block_writer.stream_position()?;
+                        block_writer.seek(SeekFrom::Start(begin_header))?;
                         let compressed_body_size = (end_body - begin_body) as u32;
                         (link_header.len() as u32 + compressed_body_size).write_options(
-                            writer,
+                            block_writer,
                             endian,
                             (),
                         )?;
-                        (link_header.len() as u32).write_options(writer, endian, ())?;
-                        (body.len() as u32).write_options(writer, endian, ())?;
-                        compressed_body_size.write_options(writer, endian, ())?;
-                        resource.class_name.write_options(writer, endian, ())?;
-                        resource.name.write_options(writer, endian, ())?;
-                        writer.seek(SeekFrom::Start(end_body))?;
-
-                        let needed_working_buffer_offset =
-                            if body.len() > (begin_resource - block_begin) as usize {
-                                body.len()
-                            } else {
-                                0
-                            };
+                        (link_header.len() as u32).write_options(block_writer, endian, ())?;
+                        (body.len() as u32).write_options(block_writer, endian, ())?;
+                        compressed_body_size.write_options(block_writer, endian, ())?;
+                        resource
+                            .class_name
+                            .write_options(block_writer, endian, ())?;
+                        resource.name.write_options(block_writer, endian, ())?;
+                        block_writer.seek(SeekFrom::Start(end_body))?;
+
+                        let needed_working_buffer_offset = if body.len() > begin_resource as usize {
+                            body.len()
+                        } else {
+                            0
+                        };
 
                         calculated_working_buffer_offset = max(
                             needed_working_buffer_offset,
@@ -153,24 +172,27 @@ impl BigFileIo for BigFileV1_06_63_02PC {
                     }
                     (SplitData { link_header, body }, false) => {
                         (link_header.len() as u32 + body.len() as u32).write_options(
-                            writer,
+                            block_writer,
                             endian,
                             (),
                         )?;
-                        (link_header.len() as u32).write_options(writer, endian, ())?;
-                        (body.len() as u32).write_options(writer, endian, ())?;
-                        0u32.write_options(writer, endian, ())?;
-                        resource.class_name.write_options(writer, endian, ())?;
-                        resource.name.write_options(writer, endian, ())?;
-                        writer.write_all(link_header)?;
-                        writer.write_all(body)?;
+                        (link_header.len() as u32).write_options(block_writer, endian, ())?;
+                        (body.len() as u32).write_options(block_writer, endian, ())?;
+                        0u32.write_options(block_writer, endian, ())?;
+                        resource
+                            .class_name
+                            .write_options(block_writer, endian, ())?;
+                        resource.name.write_options(block_writer, endian, ())?;
+                        block_writer.write_all(link_header)?;
+                        block_writer.write_all(body)?;
                     }
                     _ => todo!(),
                 }
             }
 
-            let block_end = writer.stream_position()?;
-            let data_size = (block_end - block_begin) as u32;
+            let block_data = block_writer.get_ref();
+            writer.write_all(block_data)?;
+            let data_size = block_data.len() as u32;
             let padding = write_align_to(writer, 2048, 0x00)?;
             let padded_size = data_size + padding as u32;
 
@@ -199,8 +221,7 @@ impl BigFileIo for BigFileV1_06_63_02PC {
                 data_size,
                 working_buffer_offset,
                 first_resource_name: block.resources.first().map(|r| r.name).unwrap_or_default(),
-                // TODO: Calculate checksum using Asobo Alternate on the unpadded block while writing
-                checksum: block.checksum,
+                checksum: block_checksum(block_data),
             });
         }
 
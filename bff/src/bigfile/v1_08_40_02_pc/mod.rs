@@ -4,7 +4,7 @@ pub mod resource;
 
 use std::cmp::max;
 use std::collections::HashMap;
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 
 use binrw::{BinRead as _, BinResult, BinWrite as _};
 use block::Block;
@@ -12,6 +12,7 @@ use header::*;
 use resource::Resource;
 
 use crate::bigfile::BigFile;
+use crate::bigfile::checksum::{BlockChecksumMismatch, block_checksum, verify_block_checksums};
 use crate::bigfile::manifest::*;
 use crate::bigfile::platforms::Platform;
 use crate::bigfile::resource::ResourceData::{Data, SplitData};
@@ -89,6 +90,18 @@ impl BigFileIo for BigFileV1_08_40_02PC {
         })
     }
 
+    fn verify_checksums<R: Read + Seek>(
+        reader: &mut R,
+        _version: Version,
+        platform: Platform,
+    ) -> BffResult<Vec<BlockChecksumMismatch>> {
+        let header = Header::read_options(reader, platform.into(), ())?;
+        Ok(verify_block_checksums(
+            reader,
+            header.block_descriptions.iter().map(Into::into),
+        )?)
+    }
+
     fn write<W: Write + Seek>(
         bigfile: &BigFile,
         writer: &mut W,
@@ -106,33 +119,39 @@ impl BigFileIo for BigFileV1_08_40_02PC {
         let mut block_descriptions = Vec::with_capacity(bigfile.manifest.blocks.len());
 
         for (i, block) in bigfile.manifest.blocks.iter().enumerate() {
-            let block_begin = writer.stream_position()?;
+            let block_writer = &mut Cursor::new(Vec::new());
 
             let mut calculated_working_buffer_offset = 0usize;
 
             for block_resource in block.resources.iter() {
                 let resource = bigfile.resources.get(&block_resource.name).unwrap();
-                let begin_resource = writer.stream_position()?;
+                let begin_resource = block_writer.stream_position()?;
                 match (&resource.data, block_resource.compress.unwrap_or_default()) {
                     (Data(data), true) => {
-                        let begin_header = writer.stream_position()?;
-                        writer.seek(SeekFrom::Current(16))?;
-                        let begin_data = writer.stream_position()?;
-                        lzrs_compress_data_with_header_writer_internal(data, writer, endian, ())?;
-                        let end_data = writer.stream_position()?;
-                        writer.seek(SeekFrom::Start(begin_header))?;
-                        (data.len() as u32).write_options(writer, endian, ())?;
-                        ((end_data - begin_data) as u32).write_options(writer, endian, ())?;
-                        resource.class_name.write_options(writer, endian, ())?;
-                        resource.name.write_options(writer, endian, ())?;
-                        writer.seek(SeekFrom::Start(end_data))?;
-
-                        let needed_working_buffer_offset =
-                            if data.len() > (begin_resource - block_begin) as usize {
-                                data.len()
-                            } else {
-                                0
-                            };
+                        let begin_header = block_writer.stream_position()?;
+                        block_writer.seek(SeekFrom::Current(16))?;
+                        let begin_data = block_writer.stream_position()?;
+                        lzrs_compress_data_with_header_writer_internal(
+                            data,
+                            block_writer,
+                            endian,
+                            (),
+                        )?;
+                        let end_data = block_writer.stream_position()?;
+                        block_writer.seek(SeekFrom::Start(begin_header))?;
+                        (data.len() as u32).write_options(block_writer, endian, ())?;
+                        ((end_data - begin_data) as u32).write_options(block_writer, endian, ())?;
+                        resource
+                            .class_name
+                            .write_options(block_writer, endian, ())?;
+                        resource.name.write_options(block_writer, endian, ())?;
+                        block_writer.seek(SeekFrom::Start(end_data))?;
+
+                        let needed_working_buffer_offset = if data.len() > begin_resource as usize {
+                            data.len()
+                        } else {
+                            0
+                        };
 
                         calculated_working_buffer_offset = max(
                             needed_working_buffer_offset,
@@ -140,32 +159,40 @@ impl BigFileIo for BigFileV1_08_40_02PC {
                         );
                     }
                     (Data(data), false) => {
-                        (data.len() as u32).write_options(writer, endian, ())?;
-                        0u32.write_options(writer, endian, ())?;
-                        resource.class_name.write_options(writer, endian, ())?;
-                        resource.name.write_options(writer, endian, ())?;
-                        data.write_options(writer, endian, ())?;
+                        (data.len() as u32).write_options(block_writer, endian, ())?;
+                        0u32.write_options(block_writer, endian, ())?;
+                        resource
+                            .class_name
+                            .write_options(block_writer, endian, ())?;
+                        resource.name.write_options(block_writer, endian, ())?;
+                        data.write_options(block_writer, endian, ())?;
                     }
                     (SplitData { link_header, body }, true) => {
                         let data = [link_header as &[_], body].concat();
-                        let begin_header = writer.stream_position()?;
-                        writer.seek(SeekFrom::Current(16))?;
-                        let begin_data = writer.stream_position()?;
-                        lzrs_compress_data_with_header_writer_internal(&data, writer, endian, ())?;
-                        let end_data = writer.stream_position()?;
-                        writer.seek(SeekFrom::Start(begin_header))?;
-                        (data.len() as u32).write_options(writer, endian, ())?;
-                        ((end_data - begin_data) as u32).write_options(writer, endian, ())?;
-                        resource.class_name.write_options(writer, endian, ())?;
-                        resource.name.write_options(writer, endian, ())?;
-                        writer.seek(SeekFrom::Start(end_data))?;
-
-                        let needed_working_buffer_offset =
-                            if data.len() > (begin_resource - block_begin) as usize {
-                                data.len()
-                            } else {
-                                0
-                            };
+                        let begin_header = block_writer.stream_position()?;
+                        block_writer.seek(SeekFrom::Current(16))?;
+                        let begin_data = block_writer.stream_position()?;
+                        lzrs_compress_data_with_header_writer_internal(
+                            &data,
+                            block_writer,
+                            endian,
+                            (),
+                        )?;
+                        let end_data = block_writer.stream_position()?;
+                        block_writer.seek(SeekFrom::Start(begin_header))?;
+                        (data.len() as u32).write_options(block_writer, endian, ())?;
+                        ((end_data - begin_data) as u32).write_options(block_writer, endian, ())?;
+                        resource
+                            .class_name
+                            .write_options(block_writer, endian, ())?;
+                        resource.name.write_options(block_writer, endian, ())?;
+                        block_writer.seek(SeekFrom::Start(end_data))?;
+
+                        let needed_working_buffer_offset = if data.len() > begin_resource as usize {
+                            data.len()
+                        } else {
+                            0
+                        };
 
                         calculated_working_buffer_offset = max(
                             needed_working_buffer_offset,
@@ -174,21 +201,24 @@ impl BigFileIo for BigFileV1_08_40_02PC {
                     }
                     (SplitData { link_header, body }, false) => {
                         ((link_header.len() + body.len()) as u32).write_options(
-                            writer,
+                            block_writer,
                             endian,
                             (),
                         )?;
-                        0u32.write_options(writer, endian, ())?;
-                        resource.class_name.write_options(writer, endian, ())?;
-                        resource.name.write_options(writer, endian, ())?;
-                        link_header.write_options(writer, endian, ())?;
-                        body.write_options(writer, endian, ())?;
+                        0u32.write_options(block_writer, endian, ())?;
+                        resource
+                            .class_name
+                            .write_options(block_writer, endian, ())?;
+                        resource.name.write_options(block_writer, endian, ())?;
+                        link_header.write_options(block_writer, endian, ())?;
+                        body.write_options(block_writer, endian, ())?;
                     }
                 }
             }
 
-            let block_end = writer.stream_position()?;
-            let data_size = (block_end - block_begin) as u32;
+            let block_data = block_writer.get_ref();
+            writer.write_all(block_data)?;
+            let data_size = block_data.len() as u32;
             let padding = write_align_to(writer, 2048, 0x00)?;
             let padded_size = data_size + padding as u32;
 
@@ -217,8 +247,7 @@ impl BigFileIo for BigFileV1_08_40_02PC {
                 data_size,
                 working_buffer_offset,
                 first_resource_name: block.resources.first().map(|r| r.name).unwrap_or_default(),
-                // TODO: Calculate checksum using Asobo Alternate on the unpadded block while writing
-                checksum: block.checksum,
+                checksum: block_checksum(block_data),
             });
         }
 
@@ -2,6 +2,7 @@ use std::io::SeekFrom;
 
 use binrw::*;
 
+use crate::bigfile::checksum::BlockChecksumDescription;
 use crate::bigfile::versions::VersionTriple;
 use crate::helpers::FixedStringNull;
 use crate::names::Name;
@@ -18,6 +19,16 @@ pub struct BlockDescription {
     pub checksum: Option<i32>,
 }
 
+impl From<&BlockDescription> for BlockChecksumDescription {
+    fn from(block_description: &BlockDescription) -> Self {
+        Self {
+            data_size: block_description.data_size,
+            padded_size: block_description.padded_size,
+            checksum: block_description.checksum,
+        }
+    }
+}
+
 #[derive(Debug, BinRead, BinWrite, Copy, Clone)]
 #[brw(repr = u32)]
 pub enum BigFileType {
@@ -1,6 +1,14 @@
-use binrw::BinRead;
+use std::cmp::max;
+use std::io::{Seek, Write};
 
+use binrw::{BinRead, BinResult, BinWrite as _, Endian};
+
+use super::header::DataDescription;
 use super::resource::Resource;
+use crate::bigfile::BigFile;
+use crate::bigfile::manifest::ManifestBlock;
+use crate::bigfile::resource::ResourceData::SplitData;
+use crate::helpers::{calculated_padded, copy_repeat, write_align_to};
 
 #[derive(BinRead, Debug)]
 #[br(import(data_count: u32))]
@@ -8,3 +16,97 @@ pub struct Data {
     #[br(count = data_count, align_after = 2048)]
     pub resources: Vec<Resource>,
 }
+
+pub struct BlockData {
+    pub data_offset: u32,
+    pub working_buffer_offset: u64,
+    pub padded_size: u64,
+    pub padding_size: u64,
+    pub decompressed_size: u64,
+    pub data_descriptions: Vec<DataDescription>,
+}
+
+// Every resource in the block is packed into a single data section so the block reads back in
+// manifest order
+pub fn write_block_data<W: Write + Seek>(
+    bigfile: &BigFile,
+    block: &ManifestBlock,
+    writer: &mut W,
+    endian: Endian,
+) -> BinResult<BlockData> {
+    let begin = writer.stream_position()?;
+
+    let mut calculated_working_buffer_offset = 0usize;
+    let mut decompressed_size = 0u64;
+
+    for block_resource in block.resources.iter() {
+        let resource = bigfile.resources.get(&block_resource.name).unwrap();
+        let compress = block_resource.compress.unwrap_or_default();
+        let begin_resource = writer.stream_position()?;
+        Resource::write_resource(resource, compress, writer, endian)?;
+
+        if let SplitData { link_header, body } = &resource.data {
+            decompressed_size += (link_header.len() + body.len()) as u64;
+
+            if compress && body.len() > (begin_resource - begin) as usize {
+                calculated_working_buffer_offset =
+                    max(body.len(), calculated_working_buffer_offset);
+            }
+        }
+    }
+
+    let size = writer.stream_position()? - begin;
+    let padding_size = write_align_to(writer, 2048, 0x00)? as u64;
+    let padded_size = size + padding_size;
+
+    let working_buffer_offset = block
+        .offset
+        .unwrap_or(calculated_padded(calculated_working_buffer_offset, 2048) as u64);
+
+    let data_descriptions = if block.resources.is_empty() {
+        Vec::new()
+    } else {
+        vec![DataDescription {
+            resource_count: block.resources.len() as u32,
+            padded_size,
+            size,
+            working_buffer_offset,
+        }]
+    };
+
+    Ok(BlockData {
+        data_offset: (begin / 2048) as u32,
+        working_buffer_offset,
+        padded_size,
+        padding_size,
+        decompressed_size,
+        data_descriptions,
+    })
+}
+
+// Writes the resources map up to and including the empty list of individually stored resources.
+// The trailing fields differ between versions and are left to the caller.
+pub fn write_resources_map<W: Write + Seek>(
+    block_data: &BlockData,
+    writer: &mut W,
+    endian: Endian,
+) -> BinResult<()> {
+    (block_data.data_descriptions.len() as u32).write_options(writer, endian, ())?;
+    block_data.data_offset.write_options(writer, endian, ())?;
+    (block_data.working_buffer_offset as u32).write_options(writer, endian, ())?;
+    0u32.write_options(writer, endian, ())?;
+    0u64.write_options(writer, endian, ())?;
+    block_data.padded_size.write_options(writer, endian, ())?;
+    block_data.padding_size.write_options(writer, endian, ())?;
+    for data_description in block_data.data_descriptions.iter() {
+        data_description.write_options(writer, endian, ())?;
+    }
+    copy_repeat(
+        writer,
+        0x00,
+        DataDescription::SIZE
+            * (DataDescription::MAX_COUNT - block_data.data_descriptions.len()) as u64,
+    )?;
+    0u32.write_options(writer, endian, ())?;
+    Ok(())
+}
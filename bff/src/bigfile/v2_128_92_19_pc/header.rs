@@ -16,7 +16,8 @@ pub struct DataDescription {
 }
 
 impl DataDescription {
-    const SIZE: u64 = 28;
+    pub const SIZE: u64 = 28;
+    pub const MAX_COUNT: usize = 52;
 }
 
 #[derive(Debug, BinRead)]
@@ -59,6 +60,10 @@ pub struct BlockDescription {
     pub data_resources_map_offset: u32,
 }
 
+impl BlockDescription {
+    pub const SIZE: u64 = 32;
+}
+
 #[derive(Debug, BinRead, BinWrite)]
 pub struct Header {
     pub version_oneple: VersionOneple,
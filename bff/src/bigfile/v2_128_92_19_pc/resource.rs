@@ -1,9 +1,9 @@
-use std::io::{Read, Seek, Write};
+use std::io::{Cursor, Read, Seek, Write};
 
 use binrw::{BinRead, BinResult, BinWrite, Endian, args, binread, parser};
 
 use crate::bigfile::resource::ResourceData::SplitData;
-use crate::lz::lz4_decompress_body_parser;
+use crate::lz::{lz4_compress_body_writer, lz4_decompress_body_parser};
 use crate::names::Name;
 
 #[parser(reader, endian)]
@@ -28,6 +28,35 @@ enum CompressionType {
     LZ4 = 2,
 }
 
+// The fixed size part of a resource used to index resources without decoding them
+#[derive(Debug, BinRead)]
+pub struct ResourceHeader {
+    pub class_name: Name,
+    pub name: Name,
+    pub link_name: Name,
+    _size: u32,
+    link_header_size: u32,
+    decompressed_body_size: u32,
+    _compression_type: CompressionType,
+    compressed_body_size: u32,
+    _zero: u32,
+}
+
+impl ResourceHeader {
+    pub const fn compress(&self) -> bool {
+        self.compressed_body_size != 0
+    }
+
+    pub const fn data_size(&self) -> u64 {
+        self.link_header_size as u64
+            + if self.compress() {
+                self.compressed_body_size as u64
+            } else {
+                self.decompressed_body_size as u64
+            }
+    }
+}
+
 #[binread]
 #[derive(Debug, Eq, PartialEq)]
 pub struct Resource {
@@ -57,23 +86,53 @@ impl Resource {
         resource: &crate::bigfile::resource::Resource,
         writer: &mut W,
         endian: Endian,
+    ) -> BinResult<()> {
+        Self::write_resource(resource, false, writer, endian)
+    }
+
+    pub fn write_resource<W: Write + Seek>(
+        resource: &crate::bigfile::resource::Resource,
+        compress: bool,
+        writer: &mut W,
+        endian: Endian,
     ) -> BinResult<()> {
         match &resource.data {
             SplitData { link_header, body } => {
+                let compressed_body = if compress {
+                    let mut compressed_body = Cursor::new(Vec::new());
+                    lz4_compress_body_writer(body, &mut compressed_body, endian, ())?;
+                    Some(compressed_body.into_inner())
+                } else {
+                    None
+                };
+
                 resource.class_name.write_options(writer, endian, ())?;
                 resource.name.write_options(writer, endian, ())?;
                 resource
                     .link_name
                     .unwrap_or_default()
                     .write_options(writer, endian, ())?;
-                (link_header.len() as u32 + body.len() as u32).write_options(writer, endian, ())?;
+                let stored_body = compressed_body.as_deref().unwrap_or(body);
+                (link_header.len() as u32 + stored_body.len() as u32).write_options(
+                    writer,
+                    endian,
+                    (),
+                )?;
                 (link_header.len() as u32).write_options(writer, endian, ())?;
                 (body.len() as u32).write_options(writer, endian, ())?;
-                CompressionType::None.write_options(writer, endian, ())?;
-                0u32.write_options(writer, endian, ())?;
+                match &compressed_body {
+                    Some(compressed_body) => {
+                        CompressionType::LZ4.write_options(writer, endian, ())?;
+                        (compressed_body.len() as u32).write_options(writer, endian, ())?;
+                    }
+                    None => {
+                        CompressionType::None.write_options(writer, endian, ())?;
+                        0u32.write_options(writer, endian, ())?;
+                    }
+                }
                 0u32.write_options(writer, endian, ())?;
                 writer.write_all(link_header)?;
-                writer.write_all(body)?;
+                writer.write_all(stored_body)?;
             }
             _ => unreachable!(),
         }
@@ -63,6 +63,10 @@ pub struct BlockDescription {
     pub data_resources_map_offset: u32,
 }
 
+impl BlockDescription {
+    pub const SIZE: u64 = 32;
+}
+
 #[derive(Debug, BinRead, BinWrite)]
 pub struct Header {
     pub version_oneple: VersionOneple,
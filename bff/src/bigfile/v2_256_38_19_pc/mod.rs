@@ -3,16 +3,19 @@ pub mod header;
 use std::collections::HashMap;
 use std::io::{Read, Seek, SeekFrom, Write};
 
-use binrw::{BinRead as _, BinResult};
+use binrw::{BinRead as _, BinResult, BinWrite as _, Endian};
 use header::{BlockDescription, Header, Resources};
 
 use crate::BffResult;
 use crate::bigfile::BigFile;
+use crate::bigfile::lazy::{BigFileIndex, IndexedResource};
 use crate::bigfile::manifest::*;
 use crate::bigfile::platforms::Platform;
 use crate::bigfile::v2_128_92_19_pc::block::*;
 use crate::bigfile::v2_128_92_19_pc::resource::Resource;
-use crate::bigfile::versions::Version;
+use crate::bigfile::v2_128_92_19_pc::{BLOCK_DESCRIPTIONS_OFFSET, index_resource};
+use crate::bigfile::versions::{Version, VersionXple};
+use crate::helpers::{calculated_padded, read_align_to, write_align_to};
 use crate::names::NameType::Asobo64;
 use crate::names::{Name, NameType};
 use crate::traits::BigFileIo;
@@ -81,6 +84,48 @@ pub fn blocks_parser(
     Ok(blocks)
 }
 
+#[binrw::parser(reader, endian)]
+pub fn blocks_index_parser(
+    block_descriptions: Vec<BlockDescription>,
+    resources: &mut HashMap<Name, IndexedResource>,
+) -> BinResult<Vec<ManifestBlock>> {
+    let mut blocks: Vec<ManifestBlock> = Vec::with_capacity(block_descriptions.len());
+
+    for block_description in block_descriptions {
+        reader.seek(SeekFrom::Start(
+            block_description.resources_map_offset as u64 * 2048,
+        ))?;
+        let block_resource_descriptions = Resources::read_options(reader, endian, ())?;
+
+        let mut block_resources = Vec::new();
+        for resource in block_resource_descriptions.resources.into_iter() {
+            reader.seek(SeekFrom::Start(resource.offset as u64 * 2048))?;
+            index_resource(reader, endian, &mut block_resources, resources)?;
+        }
+
+        reader.seek(SeekFrom::Start(
+            block_resource_descriptions.data_offset as u64 * 2048,
+        ))?;
+
+        for data_description in block_resource_descriptions.data_descriptions {
+            for _ in 0..data_description.resource_count {
+                let data_size = index_resource(reader, endian, &mut block_resources, resources)?;
+                reader.seek(SeekFrom::Current(data_size as i64))?;
+            }
+            read_align_to(reader, 2048)?;
+        }
+
+        blocks.push(ManifestBlock {
+            offset: Some(block_resource_descriptions.working_buffer_offset as u64),
+            checksum: None,
+            compress: None,
+            resources: block_resources,
+        });
+    }
+
+    Ok(blocks)
+}
+
 impl BigFileIo for BigFileV2_256_38_19PC {
     fn read<R: Read + Seek>(
         reader: &mut R,
@@ -113,12 +158,96 @@ impl BigFileIo for BigFileV2_256_38_19PC {
         })
     }
 
+    fn read_index<R: Read + Seek>(
+        reader: &mut R,
+        version: Version,
+        platform: Platform,
+    ) -> BffResult<BigFileIndex> {
+        let endian = platform.into();
+        let header = Header::read_options(reader, endian, ())?;
+
+        let mut resources = HashMap::new();
+
+        let blocks = blocks_index_parser(
+            reader,
+            endian,
+            (header.block_descriptions.inner, &mut resources),
+        )?;
+
+        Ok(BigFileIndex {
+            manifest: Manifest {
+                version,
+                version_xple: Some(header.version_oneple.into()),
+                platform,
+                bigfile_type: Some(header.bigfile_type.into()),
+                pool_manifest_unused: None,
+                incredi_builder_string: None,
+                blocks,
+                pool: None,
+            },
+            resources,
+        })
+    }
+
     fn write<W: Write + Seek>(
-        _bigfile: &BigFile,
-        _writer: &mut W,
+        bigfile: &BigFile,
+        writer: &mut W,
         _tag: Option<&str>,
     ) -> BffResult<()> {
-        todo!()
+        let endian: Endian = bigfile.manifest.platform.into();
+
+        let begin = writer.stream_position()?;
+        writer.seek(SeekFrom::Start(calculated_padded(
+            (BLOCK_DESCRIPTIONS_OFFSET
+                + 4
+                + BlockDescription::SIZE * bigfile.manifest.blocks.len() as u64)
+                as usize,
+            2048,
+        ) as u64))?;
+
+        let mut block_descriptions = Vec::with_capacity(bigfile.manifest.blocks.len());
+
+        for block in bigfile.manifest.blocks.iter() {
+            let block_data = write_block_data(bigfile, block, writer, endian)?;
+
+            let resources_map_offset = writer.stream_position()?;
+            write_resources_map(&block_data, writer, endian)?;
+            0u64.write_options(writer, endian, ())?;
+            0u64.write_options(writer, endian, ())?;
+            0u32.write_options(writer, endian, ())?;
+            write_align_to(writer, 2048, 0x00)?;
+
+            block_descriptions.push(BlockDescription {
+                unk1: 0,
+                unk2: 0,
+                unk3: 0,
+                resources_map_offset: (resources_map_offset / 2048) as u32,
+                data_resources_map_offset: block_data.data_offset,
+            });
+        }
+
+        let end = writer.stream_position()?;
+        writer.seek(SeekFrom::Start(begin))?;
+
+        let header = Header {
+            version_oneple: match bigfile.manifest.version_xple.unwrap_or(0.into()) {
+                VersionXple::Oneple(x) | VersionXple::Triple((x, _, _)) => x,
+            },
+            bigfile_type: bigfile
+                .manifest
+                .bigfile_type
+                .unwrap_or(BigFileType::Normal)
+                .into(),
+            block_description_offset: (BLOCK_DESCRIPTIONS_OFFSET / 2048) as u32,
+            unk1: 0,
+            unk2: 0,
+            unk3: 0,
+            block_descriptions: block_descriptions.into(),
+        };
+        header.write_options(writer, endian, ())?;
+
+        writer.seek(SeekFrom::Start(end))?;
+        Ok(())
     }
 
     const NAME_TYPE: NameType = Asobo64;
@@ -1,3 +1,9 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::ops::Range;
+
+use serde_json::json;
+
 use super::shared::{
     AnimationMaterial,
     AnimationMaterialModifier,
@@ -8,9 +14,57 @@ use super::shared::{
     AnimationNode,
     AnimationNodeModifier,
 };
+use crate::BffResult;
 use crate::class::trivial_class::TrivialClass;
-use crate::helpers::{DynArray, ResourceObjectLinkHeaderV1_381_67_09PC};
-use crate::traits::{Export, Import};
+use crate::helpers::gltf::{
+    Animation,
+    ChannelPath,
+    ChannelTarget,
+    GltfBuilder,
+    Interpolation,
+    Node,
+    linear_keyframes,
+    tgt_keyframes,
+};
+use crate::helpers::{
+    DynArray,
+    KeyframerVec3fComp,
+    Quat,
+    ResourceObjectLinkHeaderV1_381_67_09PC,
+    quat_from_euler,
+    quat_normalize,
+    sample_bezier_rot,
+};
+use crate::traits::{Artifact, Export, Import};
+
+const GLTF_ARTIFACT_NAME: &str = "data";
+// Frames per second used to bake curves glTF cannot represent
+const SAMPLE_RATE: f32 = 30.;
+
+fn frames(start: u16, count: u16) -> Range<usize> {
+    usize::from(start)..usize::from(start) + usize::from(count)
+}
+
+// Times from the first to the last key at SAMPLE_RATE, always including the last key
+fn resample_times(key_times: impl Iterator<Item = f32> + Clone) -> impl Iterator<Item = f32> {
+    let first = key_times.clone().reduce(f32::min).unwrap_or_default();
+    let last = key_times.reduce(f32::max).unwrap_or(first);
+    let frames = ((last - first) * SAMPLE_RATE).ceil() as usize;
+    (0..=frames).map(move |frame| (first + frame as f32 / SAMPLE_RATE).min(last))
+}
+
+fn push_vec3_channel(
+    gltf: &mut GltfBuilder,
+    animation: &mut Animation,
+    target: ChannelTarget,
+    keyframer: &KeyframerVec3fComp,
+    range: Range<usize>,
+) {
+    let keyframes = keyframer.keyframes().get(range).unwrap_or_default();
+    let interpolation = keyframer.interpolation_type().gltf_interpolation();
+    let (times, outputs) = tgt_keyframes(keyframes, interpolation);
+    gltf.push_animation_channel(animation, target, interpolation, &times, &outputs);
+}
 
 #[derive(..BffStruct)]
 #[br(import(_link_header: &ResourceObjectLinkHeaderV1_381_67_09PC))]
@@ -31,5 +85,116 @@ pub struct AnimationBodyV1_381_67_09PC {
 pub type AnimationV1_381_67_09PC =
     TrivialClass<ResourceObjectLinkHeaderV1_381_67_09PC, AnimationBodyV1_381_67_09PC>;
 
-impl Export for AnimationV1_381_67_09PC {}
+impl Export for AnimationV1_381_67_09PC {
+    fn export(&self) -> BffResult<HashMap<OsString, Artifact>> {
+        let node = &self.body.animation_node;
+        let mut gltf = GltfBuilder::new();
+        let mut animation = Animation {
+            name: self.name.to_scoped_string(),
+            ..Default::default()
+        };
+        let mut messages = Vec::new();
+
+        for modifier in self.body.animation_node_modifiers.iter() {
+            let bone_name = modifier.bone_name.to_scoped_string();
+            let target = gltf.push_node(Node {
+                name: bone_name.clone(),
+                ..Default::default()
+            });
+            let channel_target = |path| ChannelTarget {
+                node: Some(target),
+                path,
+            };
+
+            push_vec3_channel(
+                &mut gltf,
+                &mut animation,
+                channel_target(ChannelPath::Translation),
+                &node.keyframer_translation,
+                frames(
+                    modifier.translation_start_frame,
+                    modifier.translation_frame_count,
+                ),
+            );
+            push_vec3_channel(
+                &mut gltf,
+                &mut animation,
+                channel_target(ChannelPath::Scale),
+                &node.keyframer_scale,
+                frames(modifier.scale_start_frame, modifier.scale_frame_count),
+            );
+
+            // Bones without compressed rotation keys fall back to the Euler bezier keys. Hermite
+            // curves over Euler angles have no quaternion cubic spline equivalent so they are
+            // resampled as linear quaternion keys.
+            let rot_keyframes = node
+                .keyframer_rot
+                .keyframes()
+                .get(frames(modifier.rot_start_frame, modifier.rot_frame_count))
+                .unwrap_or_default();
+            let (times, rotations, interpolation) = if rot_keyframes.is_empty() {
+                let bezier_keyframes = node
+                    .keyframer_bezier_rot
+                    .keyframes()
+                    .get(frames(
+                        modifier.bezier_start_frame,
+                        modifier.bezier_frame_count,
+                    ))
+                    .unwrap_or_default();
+                let (times, rotations): (Vec<f32>, Vec<Quat>) =
+                    resample_times(bezier_keyframes.iter().map(|key| key.time()))
+                        .filter_map(|time| {
+                            sample_bezier_rot(bezier_keyframes, time)
+                                .map(|euler| (time, quat_from_euler(euler)))
+                        })
+                        .unzip();
+                (times, rotations, Interpolation::Linear)
+            } else {
+                let (times, rotations): (Vec<f32>, Vec<Quat>) = linear_keyframes(rot_keyframes);
+                (
+                    times,
+                    rotations.into_iter().map(quat_normalize).collect(),
+                    node.keyframer_rot.interpolation_type().gltf_interpolation(),
+                )
+            };
+            gltf.push_animation_channel(
+                &mut animation,
+                channel_target(ChannelPath::Rotation),
+                interpolation,
+                &times,
+                &rotations,
+            );
+
+            let message_keyframes = node
+                .keyframer_message
+                .keyframes()
+                .get(frames(
+                    modifier.message_start_frame,
+                    modifier.message_frame_count,
+                ))
+                .unwrap_or_default();
+            if !message_keyframes.is_empty() {
+                messages.push(json!({
+                    "node": bone_name,
+                    "keyframes": serde_json::to_value(message_keyframes)?,
+                }));
+            }
+        }
+
+        let extras = json!({
+            "duration": self.body.duration,
+            "blending": self.body.blending,
+            "messages": messages,
+        });
+        // glTF animations must have at least one channel
+        if animation.channels.is_empty() {
+            gltf.gltf.extras = Some(extras);
+        } else {
+            animation.extras = Some(extras);
+            gltf.push_animation(animation);
+        }
+
+        gltf.into_artifacts(GLTF_ARTIFACT_NAME)
+    }
+}
 impl Import for AnimationV1_381_67_09PC {}
@@ -1,10 +1,17 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+
 use bff_derive::ReferencedNames;
 use bilge::prelude::*;
 use binrw::{BinRead, BinWrite};
+use serde::{Deserialize, Serialize};
 
+use crate::BffResult;
 use crate::class::trivial_class::TrivialClass;
+use crate::error::Error;
 use crate::helpers::ResourceObjectLinkHeaderV1_381_67_09PC;
-use crate::traits::{Export, Import};
+use crate::helpers::texture::{decode_png_gray16, encode_png_gray16};
+use crate::traits::{Artifact, Export, Import};
 
 #[bitsize(32)]
 #[derive(
@@ -38,6 +45,13 @@ impl AltitudesPacked {
     const SIZE: u32 = 8;
 }
 
+impl AltitudesUnpacked {
+    const SIZE: u32 = 16;
+}
+
+const TILE_SIZE: u32 = 4;
+const MAX_HORIZON: u16 = (1 << 12) - 1;
+
 #[derive(..BffStruct)]
 struct Internal {
     width: u32,
@@ -65,5 +79,217 @@ pub struct BinaryBodyV1_381_67_09PC {
 pub type BinaryV1_381_67_09PC =
     TrivialClass<ResourceObjectLinkHeaderV1_381_67_09PC, BinaryBodyV1_381_67_09PC>;
 
-impl Export for BinaryV1_381_67_09PC {}
-impl Import for BinaryV1_381_67_09PC {}
+// The terrain is split into 4x4 tiles. Each tile has a base altitude, the
+// horizon, and 16 offsets from it. Tiles whose offsets fit in a nibble are
+// stored packed, the rest unpacked. The altitudes index is an offset in
+// 32-bit words into the packed offsets followed by the unpacked offsets.
+impl Internal {
+    const fn dimensions(&self) -> (u32, u32) {
+        let tiles_wide = self.width / TILE_SIZE;
+        let tiles_high = match tiles_wide {
+            0 => 0,
+            _ => self.lookup.len() as u32 / tiles_wide,
+        };
+        (tiles_wide * TILE_SIZE, tiles_high * TILE_SIZE)
+    }
+
+    const fn altitudes_size(&self) -> u32 {
+        self.altitudes_packed.len() as u32 * AltitudesPacked::SIZE
+            + self.altitudes_unpacked.len() as u32 * AltitudesUnpacked::SIZE
+            + self.lookup.len() as u32 * 4
+    }
+
+    fn tile_offsets(&self, lookup: &LookupDescription) -> BffResult<[u8; 16]> {
+        let offset = lookup.altitudes_index().value() * 4;
+        let packed_size = self.altitudes_packed.len() as u32 * AltitudesPacked::SIZE;
+        if offset < packed_size {
+            let packed = self
+                .altitudes_packed
+                .get((offset / AltitudesPacked::SIZE) as usize)
+                .ok_or(Error::ImportBadArtifact)?;
+            Ok(std::array::from_fn(|i| {
+                let pack = &packed.altitudes[i / 2];
+                match i % 2 {
+                    0 => pack.even().value(),
+                    _ => pack.odd().value(),
+                }
+            }))
+        } else {
+            self.altitudes_unpacked
+                .get(((offset - packed_size) / AltitudesUnpacked::SIZE) as usize)
+                .map(|unpacked| unpacked.altitudes)
+                .ok_or(Error::ImportBadArtifact)
+        }
+    }
+
+    fn altitudes(&self) -> BffResult<Vec<u16>> {
+        let (width, height) = self.dimensions();
+        let tiles_wide = width / TILE_SIZE;
+        let mut altitudes = vec![0; (width * height) as usize];
+        for (i, lookup) in self.lookup.iter().enumerate() {
+            let tile_x = i as u32 % tiles_wide * TILE_SIZE;
+            let tile_y = i as u32 / tiles_wide * TILE_SIZE;
+            let horizon = lookup.horizon().value();
+            for (j, offset) in self.tile_offsets(lookup)?.into_iter().enumerate() {
+                let x = tile_x + j as u32 % TILE_SIZE;
+                let y = tile_y + j as u32 / TILE_SIZE;
+                altitudes[(y * width + x) as usize] = horizon + u16::from(offset);
+            }
+        }
+        Ok(altitudes)
+    }
+
+    // Altitudes that cannot be represented, a horizon above 12 bits, a tile
+    // spanning more than 255 units or offsets past what a 20-bit altitudes
+    // index can reach, are rejected
+    fn pack(&mut self, altitudes: &[u16]) -> BffResult<()> {
+        let (width, _) = self.dimensions();
+        let tiles_wide = width / TILE_SIZE;
+
+        let tiles = (0..self.lookup.len() as u32)
+            .map(|i| {
+                let tile_x = i % tiles_wide * TILE_SIZE;
+                let tile_y = i / tiles_wide * TILE_SIZE;
+                let tile: [u16; 16] = std::array::from_fn(|j| {
+                    let x = tile_x + j as u32 % TILE_SIZE;
+                    let y = tile_y + j as u32 / TILE_SIZE;
+                    altitudes[(y * width + x) as usize]
+                });
+                let horizon = tile.iter().copied().min().unwrap_or(0);
+                let span = tile.iter().copied().max().unwrap_or(0) - horizon;
+                if horizon > MAX_HORIZON || span > u16::from(u8::MAX) {
+                    return Err(Error::ImportBadArtifact);
+                }
+                let offsets = tile.map(|altitude| (altitude - horizon) as u8);
+                Ok((horizon, offsets))
+            })
+            .collect::<BffResult<Vec<_>>>()?;
+
+        let is_packed = |offsets: &[u8; 16]| offsets.iter().all(|offset| *offset < 16);
+        let packed_count = tiles.iter().filter(|(_, o)| is_packed(o)).count() as u32;
+        // The highest altitudes index is the start of the last unpacked tile,
+        // or of the last packed tile when none are unpacked
+        let unpacked_count = tiles.len() as u32 - packed_count;
+        let last_index = match unpacked_count {
+            0 => packed_count.saturating_sub(1) * AltitudesPacked::SIZE / 4,
+            _ => {
+                (packed_count * AltitudesPacked::SIZE
+                    + (unpacked_count - 1) * AltitudesUnpacked::SIZE)
+                    / 4
+            }
+        };
+        if last_index > u20::MAX.value() {
+            return Err(Error::ImportBadArtifact);
+        }
+
+        self.altitudes_packed.clear();
+        self.altitudes_unpacked.clear();
+        self.lookup.clear();
+        for (horizon, offsets) in tiles {
+            let altitudes_index = if is_packed(&offsets) {
+                let index = self.altitudes_packed.len() as u32 * AltitudesPacked::SIZE / 4;
+                self.altitudes_packed.push(AltitudesPacked {
+                    altitudes: std::array::from_fn(|i| {
+                        AltitudePack::new(u4::new(offsets[i * 2 + 1]), u4::new(offsets[i * 2]))
+                    }),
+                });
+                index
+            } else {
+                let index = (packed_count * AltitudesPacked::SIZE
+                    + self.altitudes_unpacked.len() as u32 * AltitudesUnpacked::SIZE)
+                    / 4;
+                self.altitudes_unpacked
+                    .push(AltitudesUnpacked { altitudes: offsets });
+                index
+            };
+            self.lookup.push(LookupDescription::new(
+                u12::new(horizon),
+                u20::new(altitudes_index),
+            ));
+        }
+
+        self.altitudes_packed_size = self.altitudes_packed.len() as u32;
+        self.altitudes_total_size = (self.altitudes_packed.len() as u32 * AltitudesPacked::SIZE
+            + self.altitudes_unpacked.len() as u32 * AltitudesUnpacked::SIZE)
+            / 4
+            + 1;
+
+        Ok(())
+    }
+}
+
+// Only the denominator is read back, the scale is there for convenience
+#[derive(Serialize, Deserialize)]
+struct HeightmapMetadata {
+    width: u32,
+    height: u32,
+    denominator: f32,
+    scale: f32,
+}
+
+impl Export for BinaryV1_381_67_09PC {
+    fn export(&self) -> BffResult<HashMap<OsString, Artifact>> {
+        let internal = &self.body.data;
+        let (width, height) = internal.dimensions();
+        let heightmap = encode_png_gray16(width, height, &internal.altitudes()?)?;
+        let metadata = HeightmapMetadata {
+            width,
+            height,
+            denominator: internal.denominator,
+            scale: 1.0 / internal.denominator,
+        };
+
+        Ok(HashMap::from([
+            (OsString::from("heightmap"), Artifact::Png(heightmap)),
+            (
+                OsString::from("metadata"),
+                Artifact::Json(serde_json::to_string_pretty(&metadata)?),
+            ),
+        ]))
+    }
+}
+
+impl Import for BinaryV1_381_67_09PC {
+    fn import(&mut self, artifacts: &HashMap<OsString, Artifact>) -> BffResult<()> {
+        let heightmap_name = OsString::from("heightmap");
+        let Artifact::Png(heightmap) = artifacts
+            .get(&heightmap_name)
+            .ok_or(Error::ImportBadArtifact)?
+        else {
+            return Err(Error::ImportBadArtifact);
+        };
+
+        let (width, height, altitudes) = decode_png_gray16(heightmap)?;
+        let internal = &mut self.body.data;
+        if (width, height) != internal.dimensions() {
+            return Err(Error::ImportBadArtifact);
+        }
+
+        let metadata_name = OsString::from("metadata");
+        let denominator = match artifacts.get(&metadata_name) {
+            Some(Artifact::Json(metadata)) => {
+                let metadata: HeightmapMetadata = serde_json::from_str(metadata)?;
+                if (metadata.width, metadata.height) != (width, height) {
+                    return Err(Error::ImportBadArtifact);
+                }
+                metadata.denominator
+            }
+            Some(_) => return Err(Error::ImportBadArtifact),
+            None => internal.denominator,
+        };
+
+        // Keep the original encoding of an unedited heightmap so that it
+        // roundtrips byte for byte
+        if internal.altitudes()? == altitudes {
+            internal.denominator = denominator;
+            return Ok(());
+        }
+
+        let old_size = internal.altitudes_size();
+        internal.pack(&altitudes)?;
+        internal.denominator = denominator;
+        self.body.data_size = self.body.data_size - old_size + internal.altitudes_size();
+
+        Ok(())
+    }
+}
@@ -7,6 +7,7 @@ use crate::BffResult;
 use crate::class::trivial_class::TrivialClass;
 use crate::error::Error;
 use crate::helpers::ResourceObjectLinkHeaderV1_06_63_02PC;
+use crate::helpers::texture::reencode_dds_from_png;
 use crate::traits::{Artifact, Export, Import};
 
 #[derive(..BffStruct)]
@@ -51,12 +52,12 @@ impl Export for BitmapV1_06_63_02PC {
 impl Import for BitmapV1_06_63_02PC {
     fn import(&mut self, artifacts: &HashMap<OsString, Artifact>) -> BffResult<()> {
         let data_name = OsString::from("data");
-        let (Artifact::Dds(data) | Artifact::Binary(data)) =
-            artifacts.get(&data_name).ok_or(Error::ImportBadArtifact)?
-        else {
-            return Err(Error::ImportBadArtifact);
-        };
-        self.body.data.clone_from(data);
+        match artifacts.get(&data_name).ok_or(Error::ImportBadArtifact)? {
+            Artifact::Dds(data) | Artifact::Binary(data) => self.body.data.clone_from(data),
+            // The body is a DDS file, keep its format and mipmap count
+            Artifact::Png(data) => self.body.data = reencode_dds_from_png(&self.body.data, data)?,
+            _ => return Err(Error::ImportBadArtifact),
+        }
         Ok(())
     }
 }
@@ -1,9 +1,11 @@
 use std::collections::HashMap;
 use std::ffi::OsString;
+use std::io::Cursor;
 
 use bff_derive::ReferencedNames;
 use binrw::helpers::until_eof;
 use binrw::{BinWrite, binread};
+use ddsfile::{D3DFormat, Dds, NewD3dParams};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +13,7 @@ use crate::BffResult;
 use crate::class::trivial_class::TrivialClass;
 use crate::error::Error;
 use crate::helpers::ResourceObjectLinkHeaderV1_06_63_02PC;
+use crate::helpers::texture::{dds_to_png, decode_png, png_to_dds};
 use crate::traits::{Artifact, Export, Import};
 
 #[derive(..BffStruct, Clone)]
@@ -37,33 +40,175 @@ pub struct BitmapBodyV1_291_03_06PC {
 pub type BitmapV1_291_03_06PC =
     TrivialClass<ResourceObjectLinkHeaderV1_06_63_02PC, BitmapBodyV1_291_03_06PC>;
 
+// Values of BitmapHeader.format. Only the values also used by BmFormat in
+// v1_381_67_09_pc are known. DXTn bitmaps carry a complete DDS file, A8L8 is raw
+// pixel data followed by the rest of the mip chain.
+//
+// The palettized, RGBA8888 and RGB565 codes can't be identified from the data
+// alone. A raw chain of 2 bytes per pixel is equally valid as R5G6B5, A1R5G5B5,
+// A4R4G4B4 or A8L8, 4 bytes per pixel doesn't say whether alpha is used, and
+// nothing in the header describes a palette. Until a sample of each code is
+// matched against the game, bitmaps with other codes are exported as DDS when
+// the data carries its own DDS header and as binary otherwise.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[repr(u8)]
+enum BitmapFormatV1_291_03_06PC {
+    A8L8 = 7,
+    Dxt1 = 14,
+    Dxt5 = 16,
+}
+
+impl BitmapFormatV1_291_03_06PC {
+    const fn is_dds_container(self) -> bool {
+        matches!(self, Self::Dxt1 | Self::Dxt5)
+    }
+}
+
+impl TryFrom<u8> for BitmapFormatV1_291_03_06PC {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            7 => Ok(Self::A8L8),
+            14 => Ok(Self::Dxt1),
+            16 => Ok(Self::Dxt5),
+            _ => Err(Error::UnimplementedImportExport),
+        }
+    }
+}
+
+impl From<BitmapFormatV1_291_03_06PC> for D3DFormat {
+    fn from(value: BitmapFormatV1_291_03_06PC) -> Self {
+        match value {
+            BitmapFormatV1_291_03_06PC::A8L8 => Self::A8L8,
+            BitmapFormatV1_291_03_06PC::Dxt1 => Self::DXT1,
+            BitmapFormatV1_291_03_06PC::Dxt5 => Self::DXT5,
+        }
+    }
+}
+
+impl TryFrom<D3DFormat> for BitmapFormatV1_291_03_06PC {
+    type Error = Error;
+
+    fn try_from(value: D3DFormat) -> Result<Self, Self::Error> {
+        match value {
+            D3DFormat::A8L8 => Ok(Self::A8L8),
+            D3DFormat::DXT1 => Ok(Self::Dxt1),
+            D3DFormat::DXT5 => Ok(Self::Dxt5),
+            _ => Err(Error::ImportBadArtifact),
+        }
+    }
+}
+
+fn raw_to_dds(header: &BitmapHeader, format: D3DFormat, data: &[u8]) -> BffResult<Vec<u8>> {
+    let mut dds = Dds::new_d3d(NewD3dParams {
+        height: header.height,
+        width: header.width,
+        depth: Some(1),
+        format,
+        mipmap_levels: Some(u32::from(header.mipmap_count).saturating_add(1)),
+        caps2: None,
+    })
+    .map_err(|_| Error::UnimplementedImportExport)?;
+    dds.data = data.to_vec();
+    let mut dds_writer = Cursor::new(Vec::new());
+    dds.write(&mut dds_writer)
+        .map_err(|_| Error::UnimplementedImportExport)?;
+    Ok(dds_writer.into_inner())
+}
+
+const DDS_MAGIC: &[u8] = b"DDS ";
+
+impl BitmapBodyV1_291_03_06PC {
+    fn data_artifact(&self) -> BffResult<Artifact> {
+        let header = &self.header;
+        let data = &self.data;
+        Ok(match BitmapFormatV1_291_03_06PC::try_from(header.format) {
+            Ok(format) if format.is_dds_container() => Artifact::Dds(data.clone()),
+            Ok(format) => Artifact::Dds(raw_to_dds(header, format.into(), data)?),
+            Err(_) if data.starts_with(DDS_MAGIC) => Artifact::Dds(data.clone()),
+            Err(_) => Artifact::Binary(data.clone()),
+        })
+    }
+
+    // Whether the artifact holds what exporting would produce, in which case the
+    // original data and header are kept. PNGs are compared by their pixels.
+    fn is_unchanged(&self, artifact: &Artifact) -> BffResult<bool> {
+        let Ok(Artifact::Dds(dds_data)) = self.data_artifact() else {
+            return Ok(false);
+        };
+        Ok(match artifact {
+            Artifact::Dds(data) => *data == dds_data,
+            Artifact::Png(data) => match dds_to_png(&dds_data) {
+                Ok(png_data) => decode_png(data)? == decode_png(&png_data)?,
+                Err(_) => false,
+            },
+            _ => false,
+        })
+    }
+
+    // A DDS container keeps the bytes of the artifact it was read from
+    fn set_dds(&mut self, dds: Dds, dds_data: Option<&[u8]>) -> BffResult<()> {
+        let format: BitmapFormatV1_291_03_06PC = dds
+            .get_d3d_format()
+            .ok_or(Error::ImportBadArtifact)?
+            .try_into()?;
+        let mip_map_count = dds.header.mip_map_count.unwrap_or(1);
+        self.header.format = format as u8;
+        self.header.width = dds.get_width();
+        self.header.height = dds.get_height();
+        self.header.mipmap_count = mip_map_count.saturating_sub(1).min(u32::from(u8::MAX)) as u8;
+        self.data = match dds_data {
+            _ if !format.is_dds_container() => dds.data,
+            Some(dds_data) => dds_data.to_vec(),
+            None => {
+                let mut dds_writer = Cursor::new(Vec::new());
+                dds.write(&mut dds_writer)
+                    .map_err(|_| Error::ImportBadArtifact)?;
+                dds_writer.into_inner()
+            }
+        };
+        self.header.precalculated_size = self.data.len() as u32;
+        Ok(())
+    }
+}
+
 impl Export for BitmapV1_291_03_06PC {
     fn export(&self) -> BffResult<HashMap<OsString, Artifact>> {
         let data_name = OsString::from("data");
-        // TODO: Check the header format field and do something smart with it
-        let magic = &self.body.data[..4];
-        match magic {
-            &[0x44, 0x44, 0x53, 0x20] => Ok(HashMap::from([(
-                data_name,
-                Artifact::Dds(self.body.data.clone()),
-            )])),
-            _ => Ok(HashMap::from([(
-                data_name,
-                Artifact::Binary(self.body.data.clone()),
-            )])),
-        }
+        Ok(HashMap::from([(data_name, self.body.data_artifact()?)]))
     }
 }
 
 impl Import for BitmapV1_291_03_06PC {
     fn import(&mut self, artifacts: &HashMap<OsString, Artifact>) -> BffResult<()> {
         let data_name = OsString::from("data");
-        let (Artifact::Dds(data) | Artifact::Binary(data)) =
-            artifacts.get(&data_name).ok_or(Error::ImportBadArtifact)?
-        else {
-            return Err(Error::ImportBadArtifact);
-        };
-        self.body.data.clone_from(data);
-        Ok(())
+        let artifact = artifacts.get(&data_name).ok_or(Error::ImportBadArtifact)?;
+        if self.body.is_unchanged(artifact)? {
+            return Ok(());
+        }
+        let format = BitmapFormatV1_291_03_06PC::try_from(self.body.header.format);
+        match (artifact, format) {
+            // Unknown formats are passed through untouched
+            (Artifact::Binary(data), _) | (Artifact::Dds(data), Err(_)) => {
+                self.body.data.clone_from(data);
+                Ok(())
+            }
+            (Artifact::Dds(data), Ok(_)) => {
+                let dds = Dds::read(Cursor::new(data)).map_err(|_| Error::ImportBadArtifact)?;
+                self.body.set_dds(dds, Some(data))
+            }
+            // PNGs are re-encoded to the current format and mipmap count
+            (Artifact::Png(data), Ok(format)) => {
+                let dds = png_to_dds(
+                    data,
+                    format.into(),
+                    u32::from(self.body.header.mipmap_count) + 1,
+                    false,
+                )?;
+                self.body.set_dds(dds, None)
+            }
+            _ => Err(Error::ImportBadArtifact),
+        }
     }
 }
@@ -8,6 +8,7 @@ use ddsfile::{Caps2, D3DFormat, Dds, NewD3dParams};
 use crate::BffResult;
 use crate::class::trivial_class::TrivialClass;
 use crate::error::Error;
+use crate::helpers::texture::png_to_dds;
 use crate::names::Name;
 use crate::traits::{Artifact, Export, Import};
 
@@ -129,33 +130,46 @@ impl Export for BitmapV1_381_67_09PC {
     }
 }
 
+fn import_dds(bitmap: &mut BitmapV1_381_67_09PC, dds: Dds) -> BffResult<()> {
+    let format = dds
+        .get_d3d_format()
+        .ok_or(Error::UnimplementedImportExport)?
+        .try_into()?;
+    bitmap.link_header.width = dds.get_width();
+    bitmap.link_header.height = dds.get_height();
+    bitmap.link_header.precalculated_size = match format {
+        BmFormat::BmA8l8 => 0,
+        _ => dds.data.len() as u32,
+    };
+    let mip_map_count = dds.header.mip_map_count.unwrap_or(1);
+    bitmap.link_header.mipmap_count = mip_map_count.saturating_sub(1).min(u32::from(u8::MAX)) as u8;
+    bitmap.link_header.format0 = if dds.header.caps2.contains(Caps2::CUBEMAP) {
+        BmFormat::BmMultipleBitmaps
+    } else {
+        format
+    };
+    bitmap.link_header.format1 = format;
+    bitmap.body.data = dds.data;
+    Ok(())
+}
+
 impl Import for BitmapV1_381_67_09PC {
     fn import(&mut self, artifacts: &HashMap<OsString, Artifact>) -> BffResult<()> {
         let data_name = OsString::from("data");
-        let Artifact::Dds(data) = artifacts.get(&data_name).ok_or(Error::ImportBadArtifact)? else {
-            return Err(Error::ImportBadArtifact);
-        };
-        let dds_reader = Cursor::new(data);
-        let dds = Dds::read(dds_reader).map_err(|_| Error::ImportBadArtifact)?;
-        let format = dds
-            .get_d3d_format()
-            .ok_or(Error::UnimplementedImportExport)?
-            .try_into()?;
-        self.link_header.width = dds.get_width();
-        self.link_header.height = dds.get_height();
-        self.link_header.precalculated_size = match format {
-            BmFormat::BmA8l8 => 0,
-            _ => dds.data.len() as u32,
-        };
-        let mip_map_count = dds.header.mip_map_count.unwrap_or(1);
-        self.link_header.mipmap_count = mip_map_count.saturating_sub(1).min(u32::from(u8::MAX)) as u8;
-        self.link_header.format0 = if dds.header.caps2.contains(Caps2::CUBEMAP) {
-            BmFormat::BmMultipleBitmaps
-        } else {
-            format
+        let dds = match artifacts.get(&data_name).ok_or(Error::ImportBadArtifact)? {
+            Artifact::Dds(data) => {
+                let dds_reader = Cursor::new(data);
+                Dds::read(dds_reader).map_err(|_| Error::ImportBadArtifact)?
+            }
+            // PNGs are re-encoded to the current format and mipmap count
+            Artifact::Png(data) => png_to_dds(
+                data,
+                self.link_header.format1.try_into()?,
+                u32::from(self.link_header.mipmap_count) + 1,
+                matches!(self.link_header.bitmap_class, BitmapClass::Cubemap),
+            )?,
+            _ => return Err(Error::ImportBadArtifact),
         };
-        self.link_header.format1 = format;
-        self.body.data = dds.data;
-        Ok(())
+        import_dds(self, dds)
     }
 }
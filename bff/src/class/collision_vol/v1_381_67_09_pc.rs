@@ -1,7 +1,12 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+
+use crate::BffResult;
 use crate::class::trivial_class::TrivialClass;
+use crate::helpers::obj::ObjWriter;
 use crate::helpers::{DynArray, Mat4f, ObjectLinkHeaderV1_381_67_09PC};
 use crate::names::Name;
-use crate::traits::{Export, Import};
+use crate::traits::{Artifact, Export, Import};
 
 #[derive(..BffStruct)]
 struct CollisionVolInfo {
@@ -26,5 +31,20 @@ pub struct CollisionVolBodyV1_381_67_09PC {
 pub type CollisionVolV1_381_67_09PC =
     TrivialClass<ObjectLinkHeaderV1_381_67_09PC, CollisionVolBodyV1_381_67_09PC>;
 
-impl Export for CollisionVolV1_381_67_09PC {}
+impl Export for CollisionVolV1_381_67_09PC {
+    // Each volume is the unit cube centered on the origin moved into place by its local
+    // transform. This is a debug view and is not read back on import.
+    fn export(&self) -> BffResult<HashMap<OsString, Artifact>> {
+        let mut obj = ObjWriter::new();
+        for (i, info) in self.body.collision_vol_info.iter().enumerate() {
+            obj.object(&format!("volume_{i}"));
+            obj.transformed_wireframe_box([-0.5; 3], [0.5; 3], Some(&info.local_transform));
+        }
+
+        Ok(HashMap::from([(
+            OsString::from("volumes"),
+            Artifact::Obj(obj.finish()),
+        )]))
+    }
+}
 impl Import for CollisionVolV1_381_67_09PC {}
@@ -1,6 +1,14 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::io::Seek as _;
+
 use binrw::{BinRead as _, BinResult, BinWrite as _};
+use indexmap::IndexMap;
 
+use crate::BffResult;
 use crate::class::trivial_class::TrivialClass;
+use crate::error::Error;
 use crate::helpers::{
     BffMap,
     DynArray,
@@ -9,7 +17,7 @@ use crate::helpers::{
     Vec2f,
 };
 use crate::names::Name;
-use crate::traits::{Export, Import};
+use crate::traits::{Artifact, Export, Import};
 
 #[binrw::writer(writer, endian)]
 fn write_character(c: &char) -> BinResult<()> {
@@ -21,17 +29,22 @@ fn write_character(c: &char) -> BinResult<()> {
 
 #[binrw::parser(reader, endian)]
 fn parse_character() -> BinResult<char> {
+    let pos = reader.stream_position()?;
     let bytes = <u32>::read_options(reader, endian, ())?.to_be_bytes();
     let utf8_bytes = match bytes.iter().position(|&b| b != 0) {
         Some(pos) => &bytes[pos..],
         None => &[0],
     };
-    // TODO: Handle errors
-    Ok(std::str::from_utf8(utf8_bytes)
-        .unwrap()
-        .chars()
-        .next()
-        .unwrap())
+    std::str::from_utf8(utf8_bytes)
+        .ok()
+        .and_then(|s| s.chars().next())
+        .ok_or_else(|| binrw::Error::AssertFail {
+            pos,
+            message: format!(
+                "Invalid character code point {:#010X}",
+                u32::from_be_bytes(bytes)
+            ),
+        })
 }
 
 #[derive(..BffStruct, PartialEq, Eq, Hash)]
@@ -59,8 +72,23 @@ pub struct FontsBodyV1_381_67_09PC {
 pub type FontsV1_381_67_09PC =
     TrivialClass<ResourceObjectLinkHeaderV1_381_67_09PC, FontsBodyV1_381_67_09PC>;
 
-impl Export for FontsV1_381_67_09PC {}
-impl Import for FontsV1_381_67_09PC {}
+impl Export for FontsV1_381_67_09PC {
+    fn export(&self) -> BffResult<HashMap<OsString, Artifact>> {
+        Ok(fnt_artifacts(
+            self.name,
+            &self.body.characters,
+            &self.body.material_names,
+        ))
+    }
+}
+
+impl Import for FontsV1_381_67_09PC {
+    fn import(&mut self, artifacts: &HashMap<OsString, Artifact>) -> BffResult<()> {
+        (self.body.characters, self.body.material_names) =
+            characters_from_fnt_artifacts(artifacts, &self.body.material_names)?;
+        Ok(())
+    }
+}
 
 // TODO: Shouldn't need to duplicate this just because the link header type is different
 #[derive(..BffStruct)]
@@ -73,5 +101,203 @@ pub struct FontsBodyV1_06_63_02PC {
 pub type FontsV1_06_63_02PC =
     TrivialClass<ResourceObjectLinkHeaderV1_06_63_02PC, FontsBodyV1_06_63_02PC>;
 
-impl Export for FontsV1_06_63_02PC {}
-impl Import for FontsV1_06_63_02PC {}
+impl Export for FontsV1_06_63_02PC {
+    fn export(&self) -> BffResult<HashMap<OsString, Artifact>> {
+        Ok(fnt_artifacts(
+            self.name,
+            &self.body.characters,
+            &self.body.material_names,
+        ))
+    }
+}
+
+impl Import for FontsV1_06_63_02PC {
+    fn import(&mut self, artifacts: &HashMap<OsString, Artifact>) -> BffResult<()> {
+        (self.body.characters, self.body.material_names) =
+            characters_from_fnt_artifacts(artifacts, &self.body.material_names)?;
+        Ok(())
+    }
+}
+
+// AngelCode BMFont text descriptors use integer pixel rectangles while the
+// characters store UVs. The font doesn't know the size of its bitmaps so the
+// pixel values are written against a nominal texture size, flagged by the
+// nominalScale key, and the exact UVs and descent are kept as extra keys, which
+// BMFont readers ignore. On import the exact values win as long as they still
+// agree with the pixel rectangle. Pages are materials, not image files, so the
+// material name is kept in its own key next to a file name derived from it.
+const FNT_SCALE: u32 = 1024;
+const MAX_FNT_PAGES: usize = 256;
+
+fn name_string(name: Name) -> String {
+    name.to_scoped_string().unwrap_or_default()
+}
+
+fn fnt_artifacts(
+    name: Name,
+    characters: &BffMap<CharacterID, Character>,
+    material_names: &[Name],
+) -> HashMap<OsString, Artifact> {
+    let scale = FNT_SCALE as f32;
+    // The tallest character stands in for the line height, size and base
+    let line_height = characters
+        .values()
+        .map(|character| {
+            ((character.bottom_right_corner[1] - character.top_left_corner[1]) * scale).round()
+                as i32
+        })
+        .max()
+        .unwrap_or(0);
+    let mut fnt = String::new();
+    let _ = writeln!(
+        fnt,
+        "info face=\"{}\" size={line_height} bold=0 italic=0 charset=\"\" unicode=1 stretchH=100 smooth=1 aa=1 padding=0,0,0,0 spacing=0,0",
+        name_string(name)
+    );
+    let _ = writeln!(
+        fnt,
+        "common lineHeight={line_height} base={line_height} scaleW={FNT_SCALE} scaleH={FNT_SCALE} pages={} packed=0 nominalScale=1",
+        material_names.len()
+    );
+    for (id, material_name) in material_names.iter().enumerate() {
+        let material_name = name_string(*material_name);
+        let _ = writeln!(
+            fnt,
+            "page id={id} file=\"{material_name}.png\" material=\"{material_name}\""
+        );
+    }
+    let _ = writeln!(fnt, "chars count={}", characters.len());
+    for (CharacterID(c), character) in characters.iter() {
+        let [u0, v0] = character.top_left_corner;
+        let [u1, v1] = character.bottom_right_corner;
+        let x = (u0 * scale).round() as i32;
+        let y = (v0 * scale).round() as i32;
+        let width = (u1 * scale).round() as i32 - x;
+        let height = (v1 * scale).round() as i32 - y;
+        let _ = writeln!(
+            fnt,
+            "char id={} x={x} y={y} width={width} height={height} xoffset=0 yoffset=0 xadvance={width} page={} chnl=15 u0={u0} v0={v0} u1={u1} v1={v1} descent={}",
+            *c as u32, character.material_index, character.descent,
+        );
+    }
+
+    HashMap::from([(OsString::from("font"), Artifact::Fnt(fnt))])
+}
+
+// Splits a BMFont line into its tag and key=value pairs, values may be quoted
+fn parse_fnt_line(line: &str) -> Option<(&str, HashMap<&str, &str>)> {
+    let line = line.trim();
+    let (tag, mut rest) = line.split_once(' ').unwrap_or((line, ""));
+    let mut pairs = HashMap::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Some((tag, pairs));
+        }
+        let (key, value) = rest.split_once('=')?;
+        let (value, remaining) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let (value, remaining) = quoted.split_once('"')?;
+                (value, remaining)
+            }
+            None => value.split_once(' ').unwrap_or((value, "")),
+        };
+        pairs.insert(key.trim(), value);
+        rest = remaining;
+    }
+}
+
+fn fnt_value<T: std::str::FromStr>(pairs: &HashMap<&str, &str>, key: &str) -> BffResult<T> {
+    pairs
+        .get(key)
+        .and_then(|value| value.parse().ok())
+        .ok_or(Error::ImportBadArtifact)
+}
+
+fn characters_from_fnt_artifacts(
+    artifacts: &HashMap<OsString, Artifact>,
+    material_names: &[Name],
+) -> BffResult<(BffMap<CharacterID, Character>, DynArray<Name>)> {
+    let font_name = OsString::from("font");
+    let Artifact::Fnt(fnt) = artifacts.get(&font_name).ok_or(Error::ImportBadArtifact)? else {
+        return Err(Error::ImportBadArtifact);
+    };
+
+    let mut scale = [FNT_SCALE as f32; 2];
+    let mut pages = Vec::new();
+    let mut characters = IndexMap::new();
+    for line in fnt.lines().filter(|line| !line.trim().is_empty()) {
+        let (tag, pairs) = parse_fnt_line(line).ok_or(Error::ImportBadArtifact)?;
+        match tag {
+            "common" => {
+                scale = [
+                    fnt_value::<u32>(&pairs, "scaleW")? as f32,
+                    fnt_value::<u32>(&pairs, "scaleH")? as f32,
+                ];
+            }
+            "page" => {
+                let id: usize = fnt_value(&pairs, "id")?;
+                if id >= MAX_FNT_PAGES {
+                    return Err(Error::ImportBadArtifact);
+                }
+                // Without a NameContext only the existing material names can be kept
+                let material_name = match pairs
+                    .get("material")
+                    .and_then(|material| Name::from_scoped_string(material))
+                {
+                    Some(name) => name,
+                    None => *material_names.get(id).ok_or(Error::ImportBadArtifact)?,
+                };
+                if pages.len() <= id {
+                    pages.resize(id + 1, Name::default());
+                }
+                pages[id] = material_name;
+            }
+            "char" => {
+                let id: u32 = fnt_value(&pairs, "id")?;
+                let c = char::from_u32(id).ok_or(Error::ImportBadArtifact)?;
+                let x: i32 = fnt_value(&pairs, "x")?;
+                let y: i32 = fnt_value(&pairs, "y")?;
+                let width: i32 = fnt_value(&pairs, "width")?;
+                let height: i32 = fnt_value(&pairs, "height")?;
+                let pixels = [x, y, x + width, y + height];
+
+                let exact: Option<[f32; 4]> = ["u0", "v0", "u1", "v1"]
+                    .iter()
+                    .map(|key| fnt_value::<f32>(&pairs, key).ok())
+                    .collect::<Option<Vec<_>>>()
+                    .and_then(|uvs| uvs.try_into().ok());
+                let uvs = match exact {
+                    Some(uvs)
+                        if uvs.iter().zip(pixels).enumerate().all(|(i, (uv, pixel))| {
+                            (uv * scale[i % 2]).round() as i32 == pixel
+                        }) =>
+                    {
+                        uvs
+                    }
+                    _ => std::array::from_fn(|i| pixels[i] as f32 / scale[i % 2]),
+                };
+
+                characters.insert(
+                    CharacterID(c),
+                    Character {
+                        material_index: fnt_value(&pairs, "page")?,
+                        descent: fnt_value(&pairs, "descent").unwrap_or(0.),
+                        top_left_corner: [uvs[0], uvs[1]],
+                        bottom_right_corner: [uvs[2], uvs[3]],
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+
+    if characters
+        .values()
+        .any(|character| character.material_index as usize >= pages.len())
+    {
+        return Err(Error::ImportBadArtifact);
+    }
+
+    Ok((characters.into(), pages.into()))
+}
@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt::Write as _;
 use std::io::{Read, Seek, Write};
 
 use bff_derive::ReferencedNames;
@@ -7,10 +10,12 @@ use derive_more::{Deref, DerefMut};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::BffResult;
 use crate::class::trivial_class::TrivialClass;
+use crate::error::Error;
 use crate::helpers::{DynArray, ResourceObjectLinkHeaderV1_381_67_09PC, Vec2f};
 use crate::names::Name;
-use crate::traits::{Export, Import};
+use crate::traits::{Artifact, Export, Import};
 
 #[bitsize(7)]
 #[derive(TryFromBits, Debug, Serialize, Deserialize, ReferencedNames, JsonSchema)]
@@ -96,6 +101,23 @@ impl BinWrite for EncodedPoint {
     }
 }
 
+impl EncodedPoint {
+    // The smallest and largest values that fit in 20-bit signed fixed point with 2 fractional bits
+    const MIN: f32 = -131_072.;
+    const MAX: f32 = 131_071.75;
+
+    fn new(point: Vec2f) -> BffResult<Self> {
+        if point
+            .iter()
+            .all(|coordinate| (Self::MIN..=Self::MAX).contains(&((coordinate * 4.).round() / 4.)))
+        {
+            Ok(Self(point))
+        } else {
+            Err(Error::ImportBadArtifact)
+        }
+    }
+}
+
 #[derive(..BffStruct)]
 struct Road {
     r#type: RoadType,
@@ -135,5 +157,181 @@ pub struct GwRoadBodyV1_381_67_09PC {
 pub type GwRoadV1_381_67_09PC =
     TrivialClass<ResourceObjectLinkHeaderV1_381_67_09PC, GwRoadBodyV1_381_67_09PC>;
 
-impl Export for GwRoadV1_381_67_09PC {}
-impl Import for GwRoadV1_381_67_09PC {}
+impl SubType {
+    const fn svg_color(&self) -> &'static str {
+        match self {
+            Self::River => "#3080d0",
+            Self::Bridge => "#a0a0a0",
+            Self::SmallTarmacRoad | Self::NormalTarmacRoad | Self::BigTarmacRoad => "#404040",
+            Self::HighWay => "#e08020",
+            Self::SmallCircuitTrack | Self::CircuitTrack => "#c03030",
+            Self::SaltRoad | Self::SnowyDirtRoad => "#c0c0e0",
+            Self::ShortCutForest => "#308030",
+            Self::ShortCutField | Self::FieldRoad => "#a0b040",
+            _ => "#a07040",
+        }
+    }
+
+    const fn svg_width(&self) -> f32 {
+        match self {
+            Self::BigDirtRoad | Self::BigTarmacRoad | Self::HighWay | Self::River => 4.,
+            Self::NormalDirtRoad | Self::NormalTarmacRoad | Self::Bridge => 3.,
+            _ => 2.,
+        }
+    }
+}
+
+// GeoJSON representation of the roads. Coordinates are the game's world X/Z
+// plane in game units, there is no geographic projection.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "FeatureCollection")]
+struct RoadCollection {
+    features: Vec<RoadFeature>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "Feature")]
+struct RoadFeature {
+    geometry: LineString,
+    properties: RoadProperties,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+struct LineString {
+    coordinates: Vec<Position>,
+}
+
+// GeoJSON positions may have an altitude, which roads don't have
+#[derive(Serialize)]
+#[serde(transparent)]
+struct Position(Vec2f);
+
+impl<'de> Deserialize<'de> for Position {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Vec::<f32>::deserialize(deserializer)?[..] {
+            [x, y] | [x, y, _] => Ok(Self([x, y])),
+            ref position => Err(serde::de::Error::invalid_length(
+                position.len(),
+                &"a position with 2 or 3 coordinates",
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RoadProperties {
+    sub_type: SubType,
+    short_cut: bool,
+}
+
+impl GwRoadBodyV1_381_67_09PC {
+    fn svg(&self) -> String {
+        let [min_x, min_y] = self.gen_road_min;
+        let [max_x, max_y] = self.gen_road_max;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+            min_x,
+            -max_y,
+            max_x - min_x,
+            max_y - min_y,
+        );
+        // SVG's Y axis points down so flip the map to keep north up
+        svg.push_str("<g transform=\"scale(1 -1)\" fill=\"none\" stroke-linecap=\"round\">\n");
+        for road in self.roads.iter() {
+            let sub_type = road.r#type.sub_type();
+            let points = road
+                .points
+                .iter()
+                .map(|point| format!("{},{}", point[0], point[1]))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let dash = if road.r#type.short_cut() {
+                " stroke-dasharray=\"4 4\""
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                svg,
+                "<polyline stroke=\"{}\" stroke-width=\"{}\"{} points=\"{}\"/>",
+                sub_type.svg_color(),
+                sub_type.svg_width(),
+                dash,
+                points,
+            );
+        }
+        svg.push_str("</g>\n</svg>\n");
+        svg
+    }
+}
+
+impl Export for GwRoadV1_381_67_09PC {
+    fn export(&self) -> BffResult<HashMap<OsString, Artifact>> {
+        let roads = RoadCollection {
+            features: self
+                .body
+                .roads
+                .iter()
+                .map(|road| RoadFeature {
+                    geometry: LineString {
+                        coordinates: road.points.iter().map(|point| Position(point.0)).collect(),
+                    },
+                    properties: RoadProperties {
+                        sub_type: road.r#type.sub_type(),
+                        short_cut: road.r#type.short_cut(),
+                    },
+                })
+                .collect(),
+        };
+
+        Ok(HashMap::from([
+            (
+                OsString::from("roads"),
+                Artifact::GeoJson(serde_json::to_string_pretty(&roads)?),
+            ),
+            (OsString::from("overview"), Artifact::Svg(self.body.svg())),
+        ]))
+    }
+}
+
+impl Import for GwRoadV1_381_67_09PC {
+    fn import(&mut self, artifacts: &HashMap<OsString, Artifact>) -> BffResult<()> {
+        let roads_name = OsString::from("roads");
+        let Artifact::GeoJson(roads) =
+            artifacts.get(&roads_name).ok_or(Error::ImportBadArtifact)?
+        else {
+            return Err(Error::ImportBadArtifact);
+        };
+        let roads: RoadCollection = serde_json::from_str(roads)?;
+
+        let body = &mut self.body;
+        body.roads = roads
+            .features
+            .into_iter()
+            .map(|feature| {
+                Ok(Road {
+                    r#type: RoadType::new(
+                        feature.properties.sub_type,
+                        feature.properties.short_cut,
+                    ),
+                    points: feature
+                        .geometry
+                        .coordinates
+                        .into_iter()
+                        .map(|position| EncodedPoint::new(position.0))
+                        .collect::<BffResult<Vec<_>>>()?
+                        .into(),
+                })
+            })
+            .collect::<BffResult<_>>()?;
+        body.road_count = body.roads.len() as u32;
+
+        // Only grow the bounds so that unedited roads keep their original ones
+        for point in body.roads.iter().flat_map(|road| road.points.iter()) {
+            body.gen_road_min = std::array::from_fn(|i| body.gen_road_min[i].min(point[i]));
+            body.gen_road_max = std::array::from_fn(|i| body.gen_road_max[i].max(point[i]));
+        }
+
+        Ok(())
+    }
+}
@@ -23,7 +23,7 @@ pub struct LodBodyV1_381_67_09PC {
     collision_spheres: DynArray<DynSphere>,
     collision_boxes: DynArray<DynBox>,
     fade: FadeDistances,
-    skin_or_mesh_or_particles_names: DynArray<Name>,
+    pub skin_or_mesh_or_particles_names: DynArray<Name>,
     zero: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
@@ -1,8 +1,14 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
 use bff_derive::ReferencedNames;
 use bilge::prelude::*;
 use binrw::{BinRead, BinWrite, args};
 
-use crate::helpers::{DynArray, RangeBeginSize, Vec2f, Vec3f};
+use crate::BffResult;
+use crate::error::{InvalidBlendIndexError, InvalidGltfError};
+use crate::helpers::gltf::{GltfBuilder, GltfComponent, GltfDocument, TARGET_ARRAY_BUFFER};
+use crate::helpers::{DynArray, RangeBeginSize, Vec2f, Vec3f, Vec4f};
 use crate::names::Name;
 
 type VertexVectorComponent = u8;
@@ -20,12 +26,12 @@ pub enum CollisionFacesRange {
 
 #[derive(..BffStruct)]
 pub struct AABBNode {
-    min: Vec3f,
+    pub min: Vec3f,
     #[br(map = |x: (u16, u16)| (x != (0, 0)).then(|| (x.0 - 1, x.1 - 1)))]
     #[bw(map = |x| x.as_ref().map(|x| (x.0 + 1, x.1 + 1)).unwrap_or((0, 0)))]
     #[serde(skip_serializing_if = "Option::is_none")]
     collision_aabb_children: Option<(u16, u16)>,
-    max: Vec3f,
+    pub max: Vec3f,
     #[br(args(collision_aabb_children.is_none()))]
     collision_faces_range: CollisionFacesRange,
 }
@@ -162,6 +168,317 @@ impl Vertices {
     }
 }
 
+// Packed vertex vectors map [0, 255] onto [-1, 1]
+pub const fn decode_vertex_vector_component(component: VertexVectorComponent) -> f32 {
+    component as f32 / 255. * 2. - 1.
+}
+
+pub fn encode_vertex_vector_component(component: f32) -> VertexVectorComponent {
+    ((component.clamp(-1., 1.) + 1.) / 2. * 255.).round() as VertexVectorComponent
+}
+
+pub fn decode_vertex_vector(vector: VertexVector3u8) -> Vec3f {
+    let [x, y, z] = vector.map(decode_vertex_vector_component);
+    let length = (x * x + y * y + z * z).sqrt();
+    if length > 0. {
+        [x / length, y / length, z / length]
+    } else {
+        [0., 0., 1.]
+    }
+}
+
+pub fn encode_vertex_vector(vector: Vec3f) -> VertexVector3u8 {
+    vector.map(encode_vertex_vector_component)
+}
+
+fn decode_tangent(tangent: VertexVector3u8, tangent_w: VertexVectorComponent) -> Vec4f {
+    let [x, y, z] = decode_vertex_vector(tangent);
+    let w = if decode_vertex_vector_component(tangent_w) < 0. {
+        -1.
+    } else {
+        1.
+    };
+    [x, y, z, w]
+}
+
+// JOINTS_0 is written as unsigned shorts. Meshes are exported without their skin so this is the
+// most joints a blend index can refer to.
+pub const MAX_JOINT_COUNT: usize = u16::MAX as usize + 1;
+
+// Blend indices are stored as floats so they are rounded and checked before becoming joints
+fn joint_index(index: VertexBlendIndex, joint_count: usize) -> BffResult<u16> {
+    let rounded = index.round();
+    if rounded >= 0. && (rounded as usize) < joint_count.min(MAX_JOINT_COUNT) {
+        Ok(rounded as u16)
+    } else {
+        Err(InvalidBlendIndexError::new(index, joint_count).into())
+    }
+}
+
+fn push_attribute<T: GltfComponent, const N: usize>(
+    gltf: &mut GltfBuilder,
+    attributes: &mut BTreeMap<String, usize>,
+    semantic: &str,
+    data: &[[T; N]],
+) {
+    let accessor = gltf.push_accessor(data, Some(TARGET_ARRAY_BUFFER), false);
+    attributes.insert(semantic.to_owned(), accessor);
+}
+
+impl Vertices {
+    // Push one accessor per vertex attribute of the vertices in range and return the glTF
+    // primitive attributes. Blend indices must refer to one of joint_count joints.
+    pub fn push_gltf_attributes(
+        &self,
+        gltf: &mut GltfBuilder,
+        range: Range<usize>,
+        joint_count: usize,
+    ) -> BffResult<BTreeMap<String, usize>> {
+        let mut attributes = BTreeMap::new();
+
+        macro_rules! push {
+            ($semantic:literal, $vertices:expr, |$v:ident| $f:expr) => {
+                push_attribute(
+                    gltf,
+                    &mut attributes,
+                    $semantic,
+                    &$vertices[range.clone()]
+                        .iter()
+                        .map(|$v| $f)
+                        .collect::<Vec<_>>(),
+                )
+            };
+        }
+
+        macro_rules! push_tangent_space {
+            ($vertices:expr) => {
+                push!("POSITION", $vertices, |v| v.position);
+                push!("NORMAL", $vertices, |v| decode_vertex_vector(v.normal));
+                push!("TANGENT", $vertices, |v| decode_tangent(
+                    v.tangent,
+                    v.tangent_w
+                ));
+                push!("TEXCOORD_0", $vertices, |v| v.uv);
+            };
+        }
+
+        match self {
+            Self::LayoutPosition(vertices) => {
+                push!("POSITION", vertices, |v| v.position);
+            }
+            Self::LayoutPositionUV(vertices) => {
+                push!("POSITION", vertices, |v| v.position);
+                push!("TEXCOORD_0", vertices, |v| v.uv);
+            }
+            Self::LayoutNoBlend(vertices) => {
+                push_tangent_space!(vertices);
+                push!("TEXCOORD_1", vertices, |v| v.luv);
+            }
+            Self::Layout1Blend(vertices) => {
+                let joints = vertices[range.clone()]
+                    .iter()
+                    .map(|v| Ok([joint_index(v.blend_index, joint_count)?, 0, 0, 0]))
+                    .collect::<BffResult<Vec<_>>>()?;
+                push_tangent_space!(vertices);
+                push_attribute(gltf, &mut attributes, "JOINTS_0", &joints);
+                push!("WEIGHTS_0", vertices, |v| [v.blend_weight, 0., 0., 0.]);
+            }
+            Self::Layout4Blend(vertices) => {
+                let joints = vertices[range.clone()]
+                    .iter()
+                    .map(|v| {
+                        let [a, b, c, d] = v.blend_indices;
+                        Ok([
+                            joint_index(a, joint_count)?,
+                            joint_index(b, joint_count)?,
+                            joint_index(c, joint_count)?,
+                            joint_index(d, joint_count)?,
+                        ])
+                    })
+                    .collect::<BffResult<Vec<_>>>()?;
+                push_tangent_space!(vertices);
+                push_attribute(gltf, &mut attributes, "JOINTS_0", &joints);
+                push!("WEIGHTS_0", vertices, |v| v.blend_weights);
+            }
+            Self::LayoutUnknown { .. } => {}
+        }
+
+        Ok(attributes)
+    }
+
+    // Pick the layout from the attributes present. JOINTS_0 with only the first weight set is
+    // 1Blend and any other JOINTS_0 is 4Blend. Without a tangent space or lightmap UVs the
+    // position only layouts are used, and everything else is NoBlend.
+    pub fn from_gltf_attributes(
+        document: &GltfDocument,
+        attributes: &BTreeMap<String, usize>,
+    ) -> BffResult<Self> {
+        let read = |semantic: &str| -> BffResult<Option<Vec<Vec<f32>>>> {
+            attributes
+                .get(semantic)
+                .map(|accessor| document.read_accessor(*accessor))
+                .transpose()
+        };
+
+        let positions: Vec<Vec3f> = read_fixed(
+            read("POSITION")?.ok_or_else(|| {
+                InvalidGltfError::new("primitive has no POSITION attribute".to_owned())
+            })?,
+            "POSITION",
+        )?;
+        let count = positions.len();
+
+        macro_rules! read_or {
+            ($semantic:literal, $default:expr) => {
+                match read($semantic)? {
+                    Some(rows) => {
+                        let values = read_fixed(rows, $semantic)?;
+                        if values.len() != count {
+                            return Err(InvalidGltfError::new(format!(
+                                "{} has {} elements but POSITION has {}",
+                                $semantic,
+                                values.len(),
+                                count
+                            ))
+                            .into());
+                        }
+                        values
+                    }
+                    None => vec![$default; count],
+                }
+            };
+        }
+
+        let tangent_space = ["NORMAL", "TANGENT", "JOINTS_0", "TEXCOORD_1"]
+            .iter()
+            .any(|semantic| attributes.contains_key(*semantic));
+        if !tangent_space {
+            if !attributes.contains_key("TEXCOORD_0") {
+                return Ok(Self::LayoutPosition(
+                    positions
+                        .into_iter()
+                        .map(|position| LayoutPosition { position })
+                        .collect(),
+                ));
+            }
+            let uvs: Vec<Vec2f> = read_or!("TEXCOORD_0", [0., 0.]);
+            return Ok(Self::LayoutPositionUV(
+                positions
+                    .into_iter()
+                    .zip(uvs)
+                    .map(|(position, uv)| LayoutPositionUV {
+                        position,
+                        unknown: 0.,
+                        uv,
+                    })
+                    .collect(),
+            ));
+        }
+
+        let normals: Vec<Vec3f> = read_or!("NORMAL", [0., 0., 1.]);
+        let tangents: Vec<Vec4f> = read_or!("TANGENT", [1., 0., 0., 1.]);
+        let uvs: Vec<Vec2f> = read_or!("TEXCOORD_0", [0., 0.]);
+
+        let tangent_space = |i: usize| {
+            let [x, y, z, w] = tangents[i];
+            (
+                encode_vertex_vector([x, y, z]),
+                encode_vertex_vector_component(w),
+                encode_vertex_vector(normals[i]),
+            )
+        };
+
+        if attributes.contains_key("JOINTS_0") {
+            let joints: Vec<Vec4f> = read_or!("JOINTS_0", [0.; 4]);
+            let weights: Vec<Vec4f> = read_or!("WEIGHTS_0", [1., 0., 0., 0.]);
+
+            if weights.iter().all(|w| w[1..].iter().all(|w| *w == 0.)) {
+                return Ok(Self::Layout1Blend(
+                    (0..count)
+                        .map(|i| {
+                            let (tangent, tangent_w, normal) = tangent_space(i);
+                            Layout1Blend {
+                                position: positions[i],
+                                tangent,
+                                tangent_w,
+                                normal,
+                                normal_w: VertexVectorComponent::MAX,
+                                uv: uvs[i],
+                                blend_index: joints[i][0],
+                                pad2: [0; 3],
+                                blend_weight: weights[i][0],
+                            }
+                        })
+                        .collect(),
+                ));
+            }
+
+            return Ok(Self::Layout4Blend(
+                (0..count)
+                    .map(|i| {
+                        let (tangent, tangent_w, normal) = tangent_space(i);
+                        Layout4Blend {
+                            position: positions[i],
+                            tangent,
+                            tangent_w,
+                            normal,
+                            normal_w: VertexVectorComponent::MAX,
+                            uv: uvs[i],
+                            blend_indices: joints[i],
+                            blend_weights: weights[i],
+                        }
+                    })
+                    .collect(),
+            ));
+        }
+
+        let luvs: Vec<Vec2f> = read_or!("TEXCOORD_1", [0., 0.]);
+        Ok(Self::LayoutNoBlend(
+            (0..count)
+                .map(|i| {
+                    let (tangent, tangent_w, normal) = tangent_space(i);
+                    LayoutNoBlend {
+                        position: positions[i],
+                        tangent,
+                        tangent_w,
+                        normal,
+                        normal_w: VertexVectorComponent::MAX,
+                        uv: uvs[i],
+                        luv: luvs[i],
+                    }
+                })
+                .collect(),
+        ))
+    }
+
+    // Append the vertices of another buffer, handing it back if the layouts differ
+    pub fn append(&mut self, other: Self) -> Result<(), Self> {
+        match (self, other) {
+            (Self::LayoutPosition(a), Self::LayoutPosition(b)) => a.extend(b),
+            (Self::LayoutPositionUV(a), Self::LayoutPositionUV(b)) => a.extend(b),
+            (Self::LayoutNoBlend(a), Self::LayoutNoBlend(b)) => a.extend(b),
+            (Self::Layout1Blend(a), Self::Layout1Blend(b)) => a.extend(b),
+            (Self::Layout4Blend(a), Self::Layout4Blend(b)) => a.extend(b),
+            (_, other) => return Err(other),
+        }
+        Ok(())
+    }
+}
+
+fn read_fixed<const N: usize>(rows: Vec<Vec<f32>>, semantic: &str) -> BffResult<Vec<[f32; N]>> {
+    rows.into_iter()
+        .map(|row| {
+            let len = row.len();
+            <[f32; N]>::try_from(row).map_err(|_| {
+                InvalidGltfError::new(format!(
+                    "{semantic} has {len} components but {N} were expected"
+                ))
+                .into()
+            })
+        })
+        .collect()
+}
+
 #[bitsize(32)]
 #[derive(
     BinRead, DebugBits, SerializeBits, BinWrite, DeserializeBits, ReferencedNames, JsonSchemaBits,
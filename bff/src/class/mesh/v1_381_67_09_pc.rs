@@ -1,11 +1,29 @@
+use std::collections::{BTreeMap, HashMap, hash_map};
+use std::ffi::OsString;
+use std::io::Cursor;
+use std::ops::Range;
+
 use bff_derive::ReferencedNames;
 use bilge::prelude::*;
 use binrw::{BinRead, BinWrite, binrw};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use super::shared::{AABBNode, Strip, VertexGroupFlags, Vertices};
+use super::shared::{AABBNode, MAX_JOINT_COUNT, Strip, VertexGroupFlags, Vertices};
+use crate::BffResult;
 use crate::class::trivial_class::TrivialClass;
+use crate::error::InvalidGltfError;
+use crate::helpers::gltf::{
+    GltfBuilder,
+    GltfDocument,
+    MODE_TRIANGLES,
+    Material,
+    Mesh,
+    Node,
+    Primitive,
+    TARGET_ELEMENT_ARRAY_BUFFER,
+};
+use crate::helpers::obj::{ObjWriter, key_color};
 use crate::helpers::{
     BffMap,
     DynArray,
@@ -21,7 +39,7 @@ use crate::helpers::{
     Vec3i16,
 };
 use crate::names::Name;
-use crate::traits::{Export, Import};
+use crate::traits::{Artifact, Export, Import};
 
 type DisplacementVectorComponent = NumeratorFloat<i16, 1024>;
 type ShortVecWeird = [NumeratorFloat<i16, 1024>; 3];
@@ -208,5 +226,544 @@ pub struct MeshBodyV1_381_67_09PC {
 
 pub type MeshV1_381_67_09PC = TrivialClass<LinkHeader, MeshBodyV1_381_67_09PC>;
 
-impl Export for MeshV1_381_67_09PC {}
-impl Import for MeshV1_381_67_09PC {}
+const GLTF_ARTIFACT_NAME: &str = "data";
+// Indices are stored as 16-bit values so each vertex buffer is capped at this many vertices
+const MAX_VERTEX_BUFFER_LEN: usize = u16::MAX as usize + 1;
+
+// Bitfields don't implement Clone, so they are copied through their binary representation.
+// Missing bitfields start out zeroed.
+fn copy_bits<T>(value: Option<&T>) -> BffResult<T>
+where
+    T: for<'a> BinRead<Args<'a> = ()> + for<'a> BinWrite<Args<'a> = ()>,
+{
+    let mut bytes = Cursor::new(Vec::new());
+    match value {
+        Some(value) => value.write_le(&mut bytes)?,
+        None => 0u32.write_le(&mut bytes)?,
+    }
+    bytes.set_position(0);
+    Ok(T::read_le(&mut bytes)?)
+}
+
+impl VertexGroup {
+    fn tris<'a>(&self, index_buffers: &'a [IndexBufferExt]) -> &'a [Vec3i16] {
+        let begin = self.index_buffer_index_begin as usize / 3;
+        let end = begin + self.face_count as usize;
+        index_buffers
+            .get(self.index_buffer_index as usize)
+            .and_then(|index_buffer| index_buffer.tris.get(begin..end))
+            .unwrap_or_default()
+    }
+}
+
+impl MeshBodyV1_381_67_09PC {
+    // Push the mesh and any materials it uses that are not already in `materials`. Returns
+    // None if the mesh has no renderable vertex groups.
+    pub fn push_gltf_mesh(
+        &self,
+        gltf: &mut GltfBuilder,
+        materials: &mut HashMap<Name, usize>,
+        name: Option<String>,
+    ) -> BffResult<Option<usize>> {
+        let mesh_buffers = &self.mesh_buffers;
+
+        let material_indices: Vec<usize> = self
+            .material_names
+            .iter()
+            .map(|material_name| {
+                *materials.entry(*material_name).or_insert_with(|| {
+                    gltf.push_material(Material {
+                        name: material_name.to_scoped_string(),
+                        extras: None,
+                    })
+                })
+            })
+            .collect();
+
+        // Each vertex group only exports its own slice of the vertex buffer so that importing
+        // the primitives back does not duplicate the shared vertices. Groups with the same slice
+        // share accessors.
+        let mut group_attributes: HashMap<(usize, Range<usize>), BTreeMap<String, usize>> =
+            HashMap::new();
+
+        let mut primitives = Vec::new();
+        for vertex_group in mesh_buffers.vertex_groups.iter() {
+            let vertex_buffer_index = vertex_group.vertex_buffer_index as usize;
+            let Some(vertex_buffer) = mesh_buffers
+                .vertex_buffers
+                .get(vertex_buffer_index)
+                .filter(|vertex_buffer| !vertex_buffer.vertices.is_empty())
+            else {
+                continue;
+            };
+            let tris = vertex_group.tris(&mesh_buffers.index_buffers);
+
+            let vertex_count = vertex_buffer.vertices.len();
+            let first = *vertex_group.vertex_buffer_range.start() as usize;
+            let last = *vertex_group.vertex_buffer_range.end() as usize;
+            let mut range = first..(last + 1).min(vertex_count);
+            // Fall back to the whole buffer if the range is empty or doesn't cover the indices
+            if range.is_empty()
+                || tris
+                    .iter()
+                    .flatten()
+                    .any(|index| !range.contains(&(*index as u16 as usize)))
+            {
+                range = 0..vertex_count;
+            }
+
+            let attributes = match group_attributes.entry((vertex_buffer_index, range.clone())) {
+                hash_map::Entry::Occupied(entry) => entry.get().clone(),
+                hash_map::Entry::Vacant(entry) => entry
+                    .insert(vertex_buffer.vertices.push_gltf_attributes(
+                        gltf,
+                        range.clone(),
+                        MAX_JOINT_COUNT,
+                    )?)
+                    .clone(),
+            };
+            if attributes.is_empty() {
+                continue;
+            }
+
+            let indices: Vec<[u16; 1]> = tris
+                .iter()
+                .flatten()
+                .map(|index| [*index as u16 - range.start as u16])
+                .collect();
+            let indices = gltf.push_accessor(&indices, Some(TARGET_ELEMENT_ARRAY_BUFFER), false);
+
+            let material = usize::try_from(vertex_group.material_index)
+                .ok()
+                .and_then(|material| material_indices.get(material).copied());
+
+            primitives.push(Primitive {
+                attributes,
+                indices: Some(indices),
+                material,
+                mode: Some(MODE_TRIANGLES),
+                extras: None,
+            });
+        }
+
+        Ok((!primitives.is_empty()).then(|| {
+            gltf.push_mesh(Mesh {
+                name,
+                primitives,
+                extras: None,
+            })
+        }))
+    }
+
+    // Debug view of the collision triangles colored by surface type and the collision AABB
+    // tree as wireframe boxes. It is not read back on import.
+    pub fn collision_obj(&self) -> Option<String> {
+        if self.collision_faces.is_empty() && self.collision_aabbs.is_empty() {
+            return None;
+        }
+
+        let mut obj = ObjWriter::new();
+        let vertices: Vec<Vec3f> = self
+            .short_vec_weirds
+            .iter()
+            .map(|vertex| std::array::from_fn(|i| *vertex[i]))
+            .collect();
+
+        obj.object("collision_faces");
+        let mut surface_type = None;
+        for face in self.collision_faces.iter() {
+            let [Some(a), Some(b), Some(c)] = face
+                .short_vec_weirds_indices
+                .map(|index| vertices.get(index as usize).copied())
+            else {
+                continue;
+            };
+            if surface_type != Some(face.surface_type) {
+                surface_type = Some(face.surface_type);
+                obj.group(&format!("surface_type_{}", face.surface_type));
+            }
+            let color = key_color(face.surface_type.into());
+            let indices = [a, b, c].map(|vertex| obj.colored_vertex(vertex, color));
+            obj.face(indices);
+        }
+
+        obj.object("collision_aabbs");
+        for aabb in self.collision_aabbs.iter() {
+            obj.wireframe_box(aabb.min, aabb.max);
+        }
+
+        Some(obj.finish())
+    }
+}
+
+// Decode both documents so that a re-saved glTF with the same contents still matches
+fn gltf_primitives_match(current: &GltfDocument, document: &GltfDocument) -> BffResult<bool> {
+    let primitives = |document: &GltfDocument| -> Vec<&Primitive> {
+        document
+            .gltf
+            .meshes
+            .iter()
+            .flat_map(|mesh| &mesh.primitives)
+            .collect()
+    };
+    let material_name = |document: &GltfDocument, primitive: &Primitive| {
+        primitive
+            .material
+            .and_then(|material| document.gltf.materials.get(material))
+            .and_then(|material| material.name.clone())
+    };
+
+    let current_primitives = primitives(current);
+    let primitives = primitives(document);
+    if current_primitives.len() != primitives.len() {
+        return Ok(false);
+    }
+    for (current_primitive, primitive) in current_primitives.into_iter().zip(primitives) {
+        if primitive.mode.unwrap_or(MODE_TRIANGLES) != MODE_TRIANGLES
+            || material_name(current, current_primitive) != material_name(document, primitive)
+            || !current_primitive
+                .attributes
+                .keys()
+                .eq(primitive.attributes.keys())
+        {
+            return Ok(false);
+        }
+        for (semantic, accessor) in &current_primitive.attributes {
+            if current.read_accessor(*accessor)?
+                != document.read_accessor(primitive.attributes[semantic])?
+            {
+                return Ok(false);
+            }
+        }
+        let (Some(current_indices), Some(indices)) = (current_primitive.indices, primitive.indices)
+        else {
+            return Ok(false);
+        };
+        if current.read_indices(current_indices)? != document.read_indices(indices)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+impl MeshBodyV1_381_67_09PC {
+    // Whether the glTF holds exactly what exporting the mesh would produce
+    fn matches_gltf(&self, document: &GltfDocument) -> bool {
+        let mut gltf = GltfBuilder::new();
+        if self
+            .push_gltf_mesh(&mut gltf, &mut HashMap::new(), None)
+            .is_err()
+        {
+            return false;
+        }
+        let Ok(artifacts) = gltf.into_artifacts(GLTF_ARTIFACT_NAME) else {
+            return false;
+        };
+        GltfDocument::from_artifacts(&artifacts, GLTF_ARTIFACT_NAME)
+            .and_then(|current| gltf_primitives_match(&current, document))
+            .unwrap_or(false)
+    }
+
+    // Vertex buffers with an unknown layout are not exported so they can't be rebuilt
+    fn has_unknown_layout(&self) -> bool {
+        self.mesh_buffers
+            .vertex_buffers
+            .iter()
+            .any(|vertex_buffer| {
+                matches!(vertex_buffer.vertices, Vertices::LayoutUnknown { .. })
+                    && !vertex_buffer.vertices.is_empty()
+            })
+    }
+}
+
+impl Export for MeshV1_381_67_09PC {
+    fn export(&self) -> BffResult<HashMap<OsString, Artifact>> {
+        let mut gltf = GltfBuilder::new();
+        let name = self.name.to_scoped_string();
+        let mesh = self
+            .body
+            .push_gltf_mesh(&mut gltf, &mut HashMap::new(), name.clone())?;
+        gltf.push_node(Node {
+            name,
+            mesh,
+            ..Default::default()
+        });
+
+        let mut artifacts = gltf.into_artifacts(GLTF_ARTIFACT_NAME)?;
+        if let Some(collision) = self.body.collision_obj() {
+            artifacts.insert(OsString::from("collision"), Artifact::Obj(collision));
+        }
+        Ok(artifacts)
+    }
+}
+
+fn invalid_gltf(reason: String) -> crate::BffError {
+    InvalidGltfError::new(reason).into()
+}
+
+// Where a primitive's vertices and triangles ended up in the packed buffers
+struct PackedPrimitive {
+    buffer_index: usize,
+    base: usize,
+    vertex_count: usize,
+    index_buffer_index_begin: usize,
+    face_count: usize,
+    layout: usize,
+    material_index: i16,
+}
+
+impl Import for MeshV1_381_67_09PC {
+    // An unedited glTF leaves the mesh untouched since rebuilding the buffers is lossy.
+    // Otherwise primitives are packed into one vertex and index buffer per layout, splitting
+    // whenever a buffer would overflow 16-bit indices. Primitives that share attribute accessors
+    // share vertices. Node transforms are not applied. Vertex group flags and other unknowns are
+    // only carried over when the primitives still line up with the existing vertex groups.
+    // Everything is parsed and validated before the mesh is changed so a failed import leaves it
+    // untouched.
+    fn import(&mut self, artifacts: &HashMap<OsString, Artifact>) -> BffResult<()> {
+        let document = GltfDocument::from_artifacts(artifacts, GLTF_ARTIFACT_NAME)?;
+        if self.body.matches_gltf(&document) {
+            return Ok(());
+        }
+        if self.body.has_unknown_layout() {
+            return Err(invalid_gltf(
+                "the mesh has vertex buffers with an unknown layout which can't be rebuilt"
+                    .to_owned(),
+            ));
+        }
+
+        let mut material_names: Vec<Name> = self.body.material_names.to_vec();
+        let material_indices = document
+            .gltf
+            .materials
+            .iter()
+            .map(|material| {
+                let Some(name) = material.name.as_deref().and_then(Name::from_scoped_string) else {
+                    return Ok(None);
+                };
+                let index = material_names
+                    .iter()
+                    .position(|material_name| *material_name == name)
+                    .unwrap_or_else(|| {
+                        material_names.push(name);
+                        material_names.len() - 1
+                    });
+                i16::try_from(index)
+                    .map(Some)
+                    .map_err(|_| invalid_gltf(format!("material index {index} is out of range")))
+            })
+            .collect::<BffResult<Vec<_>>>()?;
+
+        let mut buffers: Vec<(Vertices, Vec<Vec3i16>)> = Vec::new();
+        let mut packed = Vec::new();
+        let mut shared_vertices: HashMap<&BTreeMap<String, usize>, (usize, usize, usize)> =
+            HashMap::new();
+
+        for primitive in document
+            .gltf
+            .meshes
+            .iter()
+            .flat_map(|mesh| &mesh.primitives)
+        {
+            if primitive.mode.is_some_and(|mode| mode != MODE_TRIANGLES) {
+                return Err(invalid_gltf(
+                    "only triangle list primitives are supported".to_owned(),
+                ));
+            }
+
+            let (buffer_index, base, vertex_count) = match shared_vertices
+                .get(&primitive.attributes)
+            {
+                Some(shared) => *shared,
+                None => {
+                    let vertices =
+                        Vertices::from_gltf_attributes(&document, &primitive.attributes)?;
+                    let vertex_count = vertices.len();
+                    if vertex_count == 0 {
+                        continue;
+                    }
+                    if vertex_count > MAX_VERTEX_BUFFER_LEN {
+                        return Err(invalid_gltf(format!(
+                            "primitive has {vertex_count} vertices but at most {MAX_VERTEX_BUFFER_LEN} are supported"
+                        )));
+                    }
+
+                    let layout = vertices.layout();
+                    let existing = buffers.iter().position(|(buffer, _)| {
+                        buffer.layout() == layout
+                            && buffer.len() + vertex_count <= MAX_VERTEX_BUFFER_LEN
+                    });
+                    let (buffer_index, base) = match existing {
+                        Some(buffer_index) => {
+                            let buffer = &mut buffers[buffer_index].0;
+                            let base = buffer.len();
+                            buffer
+                                .append(vertices)
+                                .map_err(|_| invalid_gltf("vertex layout mismatch".to_owned()))?;
+                            (buffer_index, base)
+                        }
+                        None => {
+                            buffers.push((vertices, Vec::new()));
+                            (buffers.len() - 1, 0)
+                        }
+                    };
+                    let shared = (buffer_index, base, vertex_count);
+                    shared_vertices.insert(&primitive.attributes, shared);
+                    shared
+                }
+            };
+
+            let indices = match primitive.indices {
+                Some(indices) => document.read_indices(indices)?,
+                None => (0..vertex_count as u32).collect(),
+            };
+            if !indices.len().is_multiple_of(3) {
+                return Err(invalid_gltf(format!(
+                    "primitive has {} indices which is not a multiple of 3",
+                    indices.len()
+                )));
+            }
+            if let Some(index) = indices
+                .iter()
+                .find(|index| **index as usize >= vertex_count)
+            {
+                return Err(invalid_gltf(format!(
+                    "index {index} is out of range for {vertex_count} vertices"
+                )));
+            }
+
+            let (vertices, tris) = &mut buffers[buffer_index];
+            let index_buffer_index_begin = tris.len() * 3;
+            tris.extend(
+                indices
+                    .chunks_exact(3)
+                    .map(|tri| [0, 1, 2].map(|i| (base + tri[i] as usize) as u16 as i16)),
+            );
+
+            packed.push(PackedPrimitive {
+                buffer_index,
+                base,
+                vertex_count,
+                index_buffer_index_begin,
+                face_count: indices.len() / 3,
+                layout: vertices.layout(),
+                material_index: primitive
+                    .material
+                    .and_then(|material| material_indices.get(material).copied().flatten())
+                    .unwrap_or(-1),
+            });
+        }
+
+        let mesh_buffers = &self.body.mesh_buffers;
+        let old_vertex_groups = &mesh_buffers.vertex_groups;
+        // The old vertex groups can only be told apart by position, so their unknowns are only
+        // kept when every primitive still lines up with a group of the same layout
+        let keep_old_vertex_groups = old_vertex_groups.len() == packed.len()
+            && old_vertex_groups
+                .iter()
+                .zip(&packed)
+                .all(|(old, primitive)| old.vertex_layout as usize == primitive.layout);
+        let new_vertex_group_flags = if keep_old_vertex_groups {
+            Vec::new()
+        } else {
+            (0..packed.len())
+                .map(|_| copy_bits(old_vertex_groups.first().map(|g| &g.flags)))
+                .collect::<BffResult<Vec<_>>>()?
+        };
+        let buffer_flags = (0..buffers.len())
+            .map(|i| {
+                let old_vertex_buffer = mesh_buffers
+                    .vertex_buffers
+                    .get(i)
+                    .or(mesh_buffers.vertex_buffers.first());
+                let old_index_buffer = mesh_buffers
+                    .index_buffers
+                    .get(i)
+                    .or(mesh_buffers.index_buffers.first());
+                Ok((
+                    copy_bits(old_vertex_buffer.map(|b| &b.flags))?,
+                    copy_bits(old_index_buffer.map(|b| &b.flags))?,
+                ))
+            })
+            .collect::<BffResult<Vec<_>>>()?;
+
+        // Nothing below can fail
+        self.body.material_names = material_names.into();
+
+        let mesh_buffers = &mut self.body.mesh_buffers;
+        let old_vertex_groups = std::mem::take(&mut mesh_buffers.vertex_groups.inner);
+        let mut old_vertex_groups = if keep_old_vertex_groups {
+            old_vertex_groups
+        } else {
+            Vec::new()
+        }
+        .into_iter();
+        let mut new_vertex_group_flags = new_vertex_group_flags.into_iter();
+        mesh_buffers.vertex_groups = packed
+            .into_iter()
+            .filter_map(|primitive| {
+                let (quad_range, flags, zero, vertex_buffer_range_begin_or_zero, unused1s) =
+                    match old_vertex_groups.next() {
+                        Some(old) => (
+                            old.quad_range,
+                            old.flags,
+                            old.zero,
+                            if old.vertex_buffer_range_begin_or_zero == 0 {
+                                0
+                            } else {
+                                primitive.base as u32
+                            },
+                            old.unused1s,
+                        ),
+                        None => (
+                            (0..0).into(),
+                            new_vertex_group_flags.next()?,
+                            0,
+                            0,
+                            Vec::new().into(),
+                        ),
+                    };
+
+                Some(VertexGroup {
+                    vertex_buffer_index: primitive.buffer_index as u32,
+                    index_buffer_index: primitive.buffer_index as u32,
+                    quad_range,
+                    flags,
+                    vertex_buffer_range: (primitive.base as u16
+                        ..=(primitive.base + primitive.vertex_count - 1) as u16)
+                        .into(),
+                    vertex_count: primitive.vertex_count as u32,
+                    index_buffer_index_begin: primitive.index_buffer_index_begin as u32,
+                    face_count: primitive.face_count as u32,
+                    zero,
+                    vertex_buffer_range_begin_or_zero,
+                    vertex_layout: primitive.layout as u16,
+                    material_index: primitive.material_index,
+                    unused1s,
+                })
+            })
+            .collect::<Vec<_>>()
+            .into();
+
+        let (vertex_buffers, index_buffers) = buffers
+            .into_iter()
+            .zip(buffer_flags)
+            .map(|((vertices, tris), (vertex_flags, index_flags))| {
+                (
+                    VertexBufferExt {
+                        flags: vertex_flags,
+                        vertices,
+                    },
+                    IndexBufferExt {
+                        flags: index_flags,
+                        tris,
+                    },
+                )
+            })
+            .unzip::<_, _, Vec<_>, Vec<_>>();
+        mesh_buffers.vertex_buffers = vertex_buffers.into();
+        mesh_buffers.index_buffers = index_buffers.into();
+
+        Ok(())
+    }
+}
@@ -15,20 +15,20 @@ use crate::traits::{Export, Import};
 #[br(import(_link_header: &ResourceObjectLinkHeaderV1_381_67_09PC))]
 pub struct NodeBodyV1_381_67_09PC {
     parent_name: Name,
-    head_child_name: Name,
+    pub head_child_name: Name,
     prev_sibling: Name,
-    next_sibling: Name,
-    lod_or_particles_name: Name,
+    pub next_sibling: Name,
+    pub lod_or_particles_name: Name,
     lod_data_or_particles_data_name: Name,
     user_define_name: Name,
     light_data_name: Name,
     bitmap_name: Name,
     unused_name2: Name,
-    rotation: Quat,
-    translation: Vec3f,
+    pub rotation: Quat,
+    pub translation: Vec3f,
     flags: u32,
     rotation2: Quat,
-    scale: f32,
+    pub scale: f32,
     scale2: f32,
     reciprocal_scale2: f32,
     unknown10: f32,
@@ -37,7 +37,7 @@ pub struct NodeBodyV1_381_67_09PC {
     display_seads_rect: Rect,
     collide_seads_rect: Rect,
     negative_four: i16,
-    world_transform_mat4: Mat4f,
+    pub world_transform_mat4: Mat4f,
 }
 
 pub type NodeV1_381_67_09PC =
@@ -1,4 +1,25 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::BffResult;
 use crate::class::trivial_class::TrivialClass;
+use crate::helpers::gltf::{
+    Animation,
+    Camera,
+    CameraPerspective,
+    ChannelPath,
+    ChannelTarget,
+    GltfBuilder,
+    Interpolation,
+    Light,
+    LightType,
+    Node,
+    linear_keyframes,
+    tgt_keyframes,
+};
 use crate::helpers::{
     DynArray,
     KeyframerFloat,
@@ -7,10 +28,22 @@ use crate::helpers::{
     KeyframerRot,
     KeyframerVec3f,
     KeyframerVec3fComp,
+    Quat,
     ResourceObjectLinkHeaderV1_381_67_09PC,
+    quat_normalize,
 };
 use crate::names::Name;
-use crate::traits::{Export, Import};
+use crate::traits::{Artifact, Export, Import};
+
+const GLTF_ARTIFACT_NAME: &str = "data";
+
+// glTF can only animate node transforms so camera and light keyframers are also baked at this
+// rate into extras
+const SAMPLE_RATE: f32 = 30.;
+
+// Used when the camera keyframers do not give a usable angle of view
+const DEFAULT_YFOV: f32 = std::f32::consts::FRAC_PI_4;
+const ZNEAR: f32 = 0.01;
 
 #[derive(..BffStruct)]
 struct RtcAnimationNode {
@@ -80,5 +113,152 @@ pub struct RtcBodyV1_381_67_09PC {
 pub type RtcV1_381_67_09PC =
     TrivialClass<ResourceObjectLinkHeaderV1_381_67_09PC, RtcBodyV1_381_67_09PC>;
 
-impl Export for RtcV1_381_67_09PC {}
+// Animation nodes, cameras, and omnis all target a Node by name so they share one glTF node per
+// name
+fn node(gltf: &mut GltfBuilder, nodes: &mut HashMap<Name, usize>, name: Name) -> usize {
+    *nodes.entry(name).or_insert_with(|| {
+        gltf.push_node(Node {
+            name: name.to_scoped_string(),
+            ..Default::default()
+        })
+    })
+}
+
+fn bake<T: Serialize>(duration: f32, sample: impl Fn(f32) -> Option<T>) -> Value {
+    let frames = (duration.max(0.) * SAMPLE_RATE).ceil() as usize;
+    let samples: Vec<(f32, T)> = (0..=frames)
+        .map(|frame| (frame as f32 / SAMPLE_RATE).min(duration))
+        .filter_map(|time| sample(time).map(|value| (time, value)))
+        .collect();
+    json!(samples)
+}
+
+impl Export for RtcV1_381_67_09PC {
+    fn export(&self) -> BffResult<HashMap<OsString, Artifact>> {
+        let body = &self.body;
+        let duration = body.duration;
+        let mut gltf = GltfBuilder::new();
+        let mut nodes = HashMap::new();
+        let mut animation = Animation {
+            name: self.name.to_scoped_string(),
+            ..Default::default()
+        };
+        let mut messages = Vec::new();
+
+        for animation_node in body.unknown1s.iter() {
+            let node_name = animation_node.unknown_node_name;
+            let target = node(&mut gltf, &mut nodes, node_name);
+            let channel_target = |path| ChannelTarget {
+                node: Some(target),
+                path,
+            };
+
+            let (times, rotations): (Vec<f32>, Vec<Quat>) =
+                linear_keyframes(animation_node.unknown0.keyframes());
+            let rotations: Vec<Quat> = rotations.into_iter().map(quat_normalize).collect();
+            gltf.push_animation_channel(
+                &mut animation,
+                channel_target(ChannelPath::Rotation),
+                Interpolation::Linear,
+                &times,
+                &rotations,
+            );
+
+            let translation = &animation_node.unknown1;
+            let interpolation = translation.interpolation_type().gltf_interpolation();
+            let (times, translations) = tgt_keyframes(translation.keyframes(), interpolation);
+            gltf.push_animation_channel(
+                &mut animation,
+                channel_target(ChannelPath::Translation),
+                interpolation,
+                &times,
+                &translations,
+            );
+
+            // The meaning of the second Vec3f keyframer is unknown so it is only baked
+            gltf.gltf.nodes[target].extras = Some(json!({
+                "flag": animation_node.rtc_animation_node_flag,
+                "unknown2": bake(duration, |t| animation_node.unknown2.sample(t)),
+            }));
+
+            let message_keyframes = animation_node.unknown3.keyframes();
+            if !message_keyframes.is_empty() {
+                messages.push(json!({
+                    "node": node_name.to_scoped_string(),
+                    "keyframes": serde_json::to_value(message_keyframes)?,
+                }));
+            }
+        }
+
+        // The angle of view is assumed to be the uncompressed keyframer in radians, like the
+        // angle_of_view of the Camera class. glTF can't animate a camera so yfov is static,
+        // sampled at t=0, and the animated angle of view is only in the camera extras.
+        for animation_camera in body.unknown2s.iter() {
+            let node_name = animation_camera.unknown_node_name;
+            let target = node(&mut gltf, &mut nodes, node_name);
+            let yfov = animation_camera
+                .unknown2
+                .sample(0.)
+                .filter(|yfov| *yfov > 0.)
+                .unwrap_or(DEFAULT_YFOV);
+            let camera = gltf.push_camera(Camera {
+                name: node_name.to_scoped_string(),
+                camera_type: "perspective".to_owned(),
+                perspective: Some(CameraPerspective {
+                    yfov,
+                    znear: ZNEAR,
+                    ..Default::default()
+                }),
+                extras: Some(json!({
+                    "flag": animation_camera.animation_camera_flag,
+                    "angle_of_view": bake(duration, |t| animation_camera.unknown2.sample(t)),
+                    "unknown0": bake(duration, |t| animation_camera.unknown0.sample(t)),
+                    "unknown1": bake(duration, |t| animation_camera.unknown1.sample(t)),
+                    "unknown3": bake(duration, |t| animation_camera.unknown3.sample(t)),
+                })),
+            });
+            gltf.gltf.nodes[target].camera = Some(camera);
+        }
+
+        // The Vec3 keyframer is assumed to be the color and the second float the range
+        for omni in body.animation_omnis.iter() {
+            let node_name = omni.unknown_node_name_name;
+            let target = node(&mut gltf, &mut nodes, node_name);
+            gltf.push_light(
+                target,
+                Light {
+                    name: node_name.to_scoped_string(),
+                    light_type: LightType::Point,
+                    color: omni
+                        .unknown0
+                        .sample(0.)
+                        .map(|color| color.map(|c| c.max(0.))),
+                    range: omni.unknown2.sample(0.).filter(|range| *range > 0.),
+                    extras: Some(json!({
+                        "flag": omni.animation_omni_flag,
+                        "color": bake(duration, |t| omni.unknown0.sample(t)),
+                        "unknown1": bake(duration, |t| omni.unknown1.sample(t)),
+                        "range": bake(duration, |t| omni.unknown2.sample(t)),
+                    })),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let extras = json!({
+            "duration": duration,
+            "messages": messages,
+            "timeline": serde_json::to_value(body.unknown30.keyframes())?,
+        });
+        // glTF animations must have at least one channel
+        if animation.channels.is_empty() {
+            gltf.gltf.extras = Some(extras);
+        } else {
+            animation.extras = Some(extras);
+            gltf.push_animation(animation);
+        }
+
+        gltf.into_artifacts(GLTF_ARTIFACT_NAME)
+    }
+}
 impl Import for RtcV1_381_67_09PC {}
@@ -1,6 +1,12 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+
+use crate::BffResult;
 use crate::class::trivial_class::TrivialClass;
+use crate::helpers::gltf::{GltfBuilder, Node, Skin};
 use crate::helpers::{
     DynArray,
+    MAT4F_IDENTITY,
     Mat4f,
     ObjectDatasFlagsV1_381_67_09PC,
     Quat,
@@ -8,9 +14,13 @@ use crate::helpers::{
     Sphere,
     Vec3,
     Vec3f,
+    mat4f_decompose,
+    mat4f_flatten,
+    mat4f_inverse,
+    mat4f_mul,
 };
 use crate::names::Name;
-use crate::traits::{Export, Import};
+use crate::traits::{Artifact, Export, Import};
 
 #[derive(..BffStruct)]
 struct Bone {
@@ -72,5 +82,85 @@ pub struct SkelBodyV1_381_67_09PC {
 pub type SkelV1_381_67_09PC =
     TrivialClass<ResourceObjectLinkHeaderV1_381_67_09PC, SkelBodyV1_381_67_09PC>;
 
-impl Export for SkelV1_381_67_09PC {}
+const GLTF_ARTIFACT_NAME: &str = "data";
+
+impl Export for SkelV1_381_67_09PC {
+    // Bone transformations are in skeleton space so each node gets its transformation relative
+    // to its parent, and the inverse bind matrices are the inverted skeleton space
+    // transformations.
+    fn export(&self) -> BffResult<HashMap<OsString, Artifact>> {
+        let bones = &self.body.bones;
+        let direct_parent_index = |index: usize| {
+            usize::try_from(bones[index].parent_index)
+                .ok()
+                .filter(|parent_index| *parent_index < bones.len())
+        };
+        // Bones whose parent chain leads back to themselves are exported as roots so that the
+        // node graph stays a tree
+        let parent_indices: Vec<Option<usize>> = (0..bones.len())
+            .map(|index| {
+                let mut ancestor = direct_parent_index(index);
+                for _ in 0..bones.len() {
+                    match ancestor {
+                        Some(parent_index) if parent_index == index => return None,
+                        Some(parent_index) => ancestor = direct_parent_index(parent_index),
+                        None => break,
+                    }
+                }
+                direct_parent_index(index)
+            })
+            .collect();
+        let parent_index = |index: usize| parent_indices[index];
+        let inverse =
+            |transformation: &Mat4f| mat4f_inverse(transformation).unwrap_or(MAT4F_IDENTITY);
+
+        let mut gltf = GltfBuilder::new();
+
+        let joints: Vec<usize> = bones
+            .iter()
+            .enumerate()
+            .map(|(index, bone)| {
+                let local = match parent_index(index) {
+                    Some(parent_index) => mat4f_mul(
+                        &bone.transformation,
+                        &inverse(&bones[parent_index].transformation),
+                    ),
+                    None => bone.transformation,
+                };
+                let (translation, rotation, scale) = mat4f_decompose(&local);
+                gltf.push_node(Node {
+                    name: bone.bone_name.to_scoped_string(),
+                    translation: Some(translation),
+                    rotation: Some(rotation),
+                    scale: Some(scale),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        for (index, joint) in joints.iter().enumerate() {
+            if let Some(parent_index) = parent_index(index) {
+                gltf.gltf.nodes[joints[parent_index]].children.push(*joint);
+            }
+        }
+
+        let inverse_bind_matrices: Vec<[f32; 16]> = bones
+            .iter()
+            .map(|bone| mat4f_flatten(&inverse(&bone.transformation)))
+            .collect();
+        let inverse_bind_matrices = gltf.push_accessor(&inverse_bind_matrices, None, false);
+
+        let skeleton = (0..bones.len())
+            .find(|index| parent_index(*index).is_none())
+            .map(|index| joints[index]);
+        gltf.push_skin(Skin {
+            name: self.name.to_scoped_string(),
+            inverse_bind_matrices: Some(inverse_bind_matrices),
+            skeleton,
+            joints,
+        });
+
+        gltf.into_artifacts(GLTF_ARTIFACT_NAME)
+    }
+}
 impl Import for SkelV1_381_67_09PC {}
@@ -21,7 +21,7 @@ struct SkinSection {
 #[derive(..BffStruct)]
 #[br(import(_link_header: &ObjectLinkHeaderV1_381_67_09PC))]
 pub struct SkinBodyV1_381_67_09PC {
-    mesh_names: DynArray<Name>,
+    pub mesh_names: DynArray<Name>,
     zeros: [u32; 4],
     one_and_a_half: f32,
     bone_name_count: u32,
@@ -1,6 +1,12 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+
+use crate::BffResult;
 use crate::class::trivial_class::TrivialClass;
+use crate::error::Error;
+use crate::helpers::spline::{HermiteSegment, SUBDIVISION_COUNT, SplineObj, spline_to_obj};
 use crate::helpers::{DynArray, ObjectLinkHeaderV1_06_63_02PC, Vec3f, Vec4f};
-use crate::traits::{Export, Import};
+use crate::traits::{Artifact, Export, Import};
 
 #[derive(..BffStruct)]
 struct Segment {
@@ -28,5 +34,55 @@ pub struct SplineBodyV1_06_63_02PC {
 
 pub type SplineV1_06_63_02PC = TrivialClass<ObjectLinkHeaderV1_06_63_02PC, SplineBodyV1_06_63_02PC>;
 
-impl Export for SplineV1_06_63_02PC {}
-impl Import for SplineV1_06_63_02PC {}
+impl HermiteSegment for Spline {
+    fn point_ids(&self) -> [u16; 2] {
+        self.point_id
+    }
+
+    fn tangent_ids(&self) -> [u16; 2] {
+        self.tangent_id
+    }
+
+    fn length(&self) -> f32 {
+        self.length
+    }
+
+    fn subdivision_vertices(&self) -> impl Iterator<Item = [Vec3f; 2]> {
+        self.segments.iter().map(|subdivision| subdivision.vertices)
+    }
+
+    fn set_subdivisions(&mut self, subdivisions: [([Vec3f; 2], f32); SUBDIVISION_COUNT]) {
+        self.length = subdivisions.iter().map(|(_, length)| length).sum();
+        self.segments = subdivisions.map(|(vertices, length)| Segment { vertices, length });
+    }
+}
+
+impl Export for SplineV1_06_63_02PC {
+    fn export(&self) -> BffResult<HashMap<OsString, Artifact>> {
+        Ok(HashMap::from([(
+            OsString::from("spline"),
+            Artifact::Obj(spline_to_obj(&self.body.points, &self.body.splines)),
+        )]))
+    }
+}
+
+impl Import for SplineV1_06_63_02PC {
+    fn import(&mut self, artifacts: &HashMap<OsString, Artifact>) -> BffResult<()> {
+        let spline_name = OsString::from("spline");
+        let Artifact::Obj(obj) = artifacts
+            .get(&spline_name)
+            .ok_or(Error::ImportBadArtifact)?
+        else {
+            return Err(Error::ImportBadArtifact);
+        };
+
+        let body = &mut self.body;
+        SplineObj {
+            points: &mut body.points,
+            segments: &mut body.splines,
+            vec: &mut body.vec,
+            length: &mut body.length,
+        }
+        .import(obj)
+    }
+}
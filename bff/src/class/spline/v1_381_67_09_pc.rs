@@ -1,6 +1,12 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+
+use crate::BffResult;
 use crate::class::trivial_class::TrivialClass;
+use crate::error::Error;
+use crate::helpers::spline::{HermiteSegment, SUBDIVISION_COUNT, SplineObj, spline_to_obj};
 use crate::helpers::{DynArray, ObjectLinkHeaderV1_381_67_09PC, Vec3f, Vec4f};
-use crate::traits::{Export, Import};
+use crate::traits::{Artifact, Export, Import};
 
 #[derive(..BffStruct)]
 struct SplineSegmentSubdivision {
@@ -29,5 +35,58 @@ pub struct SplineBodyV1_381_67_09PC {
 pub type SplineV1_381_67_09PC =
     TrivialClass<ObjectLinkHeaderV1_381_67_09PC, SplineBodyV1_381_67_09PC>;
 
-impl Export for SplineV1_381_67_09PC {}
-impl Import for SplineV1_381_67_09PC {}
+impl HermiteSegment for SplineSegment {
+    fn point_ids(&self) -> [u16; 2] {
+        self.p
+    }
+
+    fn tangent_ids(&self) -> [u16; 2] {
+        self.t
+    }
+
+    fn length(&self) -> f32 {
+        self.length
+    }
+
+    fn subdivision_vertices(&self) -> impl Iterator<Item = [Vec3f; 2]> {
+        self.spline_segment_subdivisions
+            .iter()
+            .map(|subdivision| subdivision.p)
+    }
+
+    fn set_subdivisions(&mut self, subdivisions: [([Vec3f; 2], f32); SUBDIVISION_COUNT]) {
+        self.length = subdivisions.iter().map(|(_, length)| length).sum();
+        self.spline_segment_subdivisions =
+            subdivisions.map(|(p, length)| SplineSegmentSubdivision { p, length });
+    }
+}
+
+impl Export for SplineV1_381_67_09PC {
+    fn export(&self) -> BffResult<HashMap<OsString, Artifact>> {
+        Ok(HashMap::from([(
+            OsString::from("spline"),
+            Artifact::Obj(spline_to_obj(&self.body.points, &self.body.spline_segments)),
+        )]))
+    }
+}
+
+impl Import for SplineV1_381_67_09PC {
+    fn import(&mut self, artifacts: &HashMap<OsString, Artifact>) -> BffResult<()> {
+        let spline_name = OsString::from("spline");
+        let Artifact::Obj(obj) = artifacts
+            .get(&spline_name)
+            .ok_or(Error::ImportBadArtifact)?
+        else {
+            return Err(Error::ImportBadArtifact);
+        };
+
+        let body = &mut self.body;
+        SplineObj {
+            points: &mut body.points,
+            segments: &mut body.spline_segments,
+            vec: &mut body.vec,
+            length: &mut body.length,
+        }
+        .import(obj)
+    }
+}
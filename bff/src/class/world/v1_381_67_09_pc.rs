@@ -16,12 +16,12 @@ struct Unknown2 {
 #[derive(..BffStruct)]
 #[br(import(_link_header: &ResourceObjectLinkHeaderV1_381_67_09PC))]
 pub struct WorldBodyV1_381_67_09PC {
-    node_name0: Name,
+    pub node_name0: Name,
     warp_name: Name,
     game_obj_name: Name,
     unused14: Name,
     gen_world_name: Name,
-    node_name1: Name,
+    pub node_name1: Name,
     unused17s: DynArray<u32>,
     unuseds: DynArray<u8>,
     unknown0: Mat4f,
@@ -4,6 +4,7 @@ mod asobo_alternate32;
 mod blacksheep32;
 mod kalisto32;
 mod racenet32;
+mod reverse;
 mod ubisoft64;
 
 pub use asobo_alternate32::*;
@@ -12,4 +13,5 @@ pub use asobo64::*;
 pub use blacksheep32::*;
 pub use kalisto32::*;
 pub use racenet32::*;
+pub use reverse::*;
 pub use ubisoft64::*;
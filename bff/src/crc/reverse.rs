@@ -0,0 +1,241 @@
+use std::ops::ControlFlow;
+
+use super::asobo32::CRC32_TABLE as ASOBO32_TABLE;
+use super::blacksheep32::CRC32_TABLE as BLACKSHEEP32_TABLE;
+
+// The 32-bit name hashes that are plain table driven CRCs and so can be run backwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReversibleCrc32 {
+    Asobo32,
+    AsoboAlternate32,
+    Kalisto32,
+    BlackSheep32,
+}
+
+struct Crc32Model {
+    table: &'static [u32; 256],
+    // Table index by the byte of the entry that the shifted state leaves untouched
+    inverse: [u8; 256],
+    shift_right: bool,
+    uppercase: bool,
+    // The starting value and the result are both complemented
+    complement: bool,
+}
+
+const fn inverse_table(table: &[u32; 256], shift_right: bool) -> [u8; 256] {
+    let mut inverse = [0; 256];
+    let mut seen = [false; 256];
+    let mut i = 0;
+    while i < table.len() {
+        let key = if shift_right {
+            table[i] >> 24
+        } else {
+            table[i] & 0xFF
+        } as usize;
+        assert!(!seen[key], "CRC table bytes must be unique to be inverted");
+        seen[key] = true;
+        inverse[key] = i as u8;
+        i += 1;
+    }
+    inverse
+}
+
+const ASOBO32: Crc32Model = Crc32Model {
+    table: &ASOBO32_TABLE,
+    inverse: inverse_table(&ASOBO32_TABLE, true),
+    shift_right: true,
+    uppercase: false,
+    complement: false,
+};
+
+const ASOBO_ALTERNATE32: Crc32Model = Crc32Model {
+    table: &ASOBO32_TABLE,
+    inverse: inverse_table(&ASOBO32_TABLE, false),
+    shift_right: false,
+    uppercase: false,
+    complement: false,
+};
+
+const KALISTO32: Crc32Model = Crc32Model {
+    complement: true,
+    ..ASOBO32
+};
+
+const BLACKSHEEP32: Crc32Model = Crc32Model {
+    table: &BLACKSHEEP32_TABLE,
+    inverse: inverse_table(&BLACKSHEEP32_TABLE, true),
+    shift_right: true,
+    uppercase: true,
+    complement: true,
+};
+
+impl Crc32Model {
+    const fn fold(&self, c: u8) -> u32 {
+        if self.uppercase {
+            c.to_ascii_uppercase() as u32
+        } else {
+            c.to_ascii_lowercase() as u32
+        }
+    }
+
+    const fn state(&self, value: i32) -> u32 {
+        if self.complement {
+            !value as u32
+        } else {
+            value as u32
+        }
+    }
+
+    const fn step(&self, state: u32, c: u8) -> u32 {
+        let c = self.fold(c);
+        if self.shift_right {
+            (state >> 8) ^ self.table[((c ^ state) & 0xFF) as usize]
+        } else {
+            (state << 8) ^ self.table[((c ^ (state >> 24)) & 0xFF) as usize]
+        }
+    }
+
+    // The state that step turns into `state` when given c. The shift clears one byte so the table
+    // entry can be recovered from the same byte of `state`.
+    const fn unstep(&self, state: u32, c: u8) -> u32 {
+        let c = self.fold(c);
+        if self.shift_right {
+            let index = self.inverse[(state >> 24) as usize] as u32;
+            ((state ^ self.table[index as usize]) << 8) | (index ^ c)
+        } else {
+            let index = self.inverse[(state & 0xFF) as usize] as u32;
+            ((state ^ self.table[index as usize]) >> 8) | ((index ^ c) << 24)
+        }
+    }
+}
+
+// Call f with every string of exactly `length` characters and the state reached by applying next
+// to each character in turn, stopping early if f breaks
+fn walk(
+    state: u32,
+    length: usize,
+    charset: &[u8],
+    next: &impl Fn(u32, u8) -> u32,
+    path: &mut Vec<u8>,
+    f: &mut impl FnMut(u32, &[u8]) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    if path.len() == length {
+        return f(state, path);
+    }
+    for c in charset {
+        path.push(*c);
+        walk(next(state, *c), length, charset, next, path, f)?;
+        path.pop();
+    }
+    ControlFlow::Continue(())
+}
+
+impl ReversibleCrc32 {
+    const fn model(self) -> &'static Crc32Model {
+        match self {
+            Self::Asobo32 => &ASOBO32,
+            Self::AsoboAlternate32 => &ASOBO_ALTERNATE32,
+            Self::Kalisto32 => &KALISTO32,
+            Self::BlackSheep32 => &BLACKSHEEP32,
+        }
+    }
+
+    // Characters that only differ by case hash the same
+    fn fold_charset(self, charset: &[u8]) -> Vec<u8> {
+        let model = self.model();
+        let mut folded = [false; 256];
+        charset
+            .iter()
+            .copied()
+            .filter(|c| !std::mem::replace(&mut folded[model.fold(*c) as usize], true))
+            .collect()
+    }
+
+    // How many suffixes forge_suffixes is expected to find by chance alone. Once this exceeds 1 most
+    // of the results are collisions rather than the original string.
+    pub fn expected_suffixes(self, charset: &[u8], max_length: usize) -> f64 {
+        let charset = self.fold_charset(charset).len() as f64;
+        (0..=max_length)
+            .map(|length| charset.powi(length as i32))
+            .sum::<f64>()
+            / 2f64.powi(32)
+    }
+
+    // Call f with every suffix of at most max_length characters from charset that makes prefix
+    // hash to target, shortest first, until f breaks. Each length is split into a head enumerated
+    // forwards from the prefix and a tail enumerated backwards from the target, which meet at the
+    // same state, so a length L search costs about |charset|^(L/2) time and memory instead of
+    // |charset|^L.
+    pub fn forge_suffixes(
+        self,
+        prefix: &[u8],
+        target: i32,
+        starting: i32,
+        charset: &[u8],
+        max_length: usize,
+        mut f: impl FnMut(&[u8]) -> ControlFlow<()>,
+    ) {
+        let model = self.model();
+        let charset = self.fold_charset(charset);
+
+        let prefix_state = prefix
+            .iter()
+            .fold(model.state(starting), |state, c| model.step(state, *c));
+        let target_state = model.state(target);
+        let forward = |state: u32, c: u8| model.step(state, c);
+        let backward = |state: u32, c: u8| model.unstep(state, c);
+
+        let mut suffix = Vec::new();
+        let mut heads: Vec<u8> = Vec::new();
+        let mut middles: Vec<(u32, usize)> = Vec::new();
+        let mut head_length = None;
+        for length in 0..=max_length {
+            let head = length / 2;
+            let tail = length - head;
+
+            if head_length != Some(head) {
+                heads.clear();
+                middles.clear();
+                let _ = walk(
+                    prefix_state,
+                    head,
+                    &charset,
+                    &forward,
+                    &mut Vec::new(),
+                    &mut |state, path| {
+                        middles.push((state, middles.len()));
+                        heads.extend_from_slice(path);
+                        ControlFlow::Continue(())
+                    },
+                );
+                middles.sort_unstable();
+                head_length = Some(head);
+            }
+
+            // Tails are walked from their last character so the path is reversed
+            let flow = walk(
+                target_state,
+                tail,
+                &charset,
+                &backward,
+                &mut Vec::new(),
+                &mut |state, reversed_tail| {
+                    let first = middles.partition_point(|(middle, _)| *middle < state);
+                    for (_, index) in middles[first..]
+                        .iter()
+                        .take_while(|(middle, _)| *middle == state)
+                    {
+                        suffix.clear();
+                        suffix.extend_from_slice(&heads[index * head..(index + 1) * head]);
+                        suffix.extend(reversed_tail.iter().rev());
+                        f(&suffix)?;
+                    }
+                    ControlFlow::Continue(())
+                },
+            );
+            if flow.is_break() {
+                return;
+            }
+        }
+    }
+}
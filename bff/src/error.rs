@@ -41,6 +41,13 @@ pub struct InvalidPlatformStyleError {
     pub style: Style,
 }
 
+#[derive(Debug, Constructor, Display, Error)]
+#[display("Invalid mask `{mask}`: {reason}")]
+pub struct InvalidMaskError {
+    pub mask: String,
+    pub reason: String,
+}
+
 #[derive(Debug, Constructor, Display, Error)]
 #[display("Invalid name decoding: {reason}")]
 pub struct InvalidNameDecodingError {
@@ -62,17 +69,42 @@ pub struct InvalidFatEntryError {
     pub line: String,
 }
 
+#[derive(Debug, Constructor, Display, Error)]
+#[display("Invalid glTF: {reason}")]
+pub struct InvalidGltfError {
+    pub reason: String,
+}
+
+#[derive(Debug, Constructor, Display, Error)]
+#[display("Invalid blend index {index}: expected a whole number below {joint_count}")]
+pub struct InvalidBlendIndexError {
+    pub index: f32,
+    pub joint_count: usize,
+}
+
+#[derive(Debug, Constructor, Display, Error)]
+#[display("Invalid block index {index}: the BigFile has {block_count} blocks")]
+pub struct InvalidBlockIndexError {
+    pub index: usize,
+    pub block_count: usize,
+}
+
 #[derive(Debug, Display, Error, From)]
 pub enum Error {
     BinRW(binrw::Error),
     Fmt(std::fmt::Error),
+    InvalidBlendIndex(InvalidBlendIndexError),
+    InvalidBlockIndex(InvalidBlockIndexError),
     InvalidExtension(InvalidExtensionError),
     InvalidFatEntry(InvalidFatEntryError),
+    InvalidGltf(InvalidGltfError),
+    InvalidMask(InvalidMaskError),
     InvalidNameDecoding(InvalidNameDecodingError),
     InvalidNameEncoding(InvalidNameEncodingError),
     InvalidPlatformStyle(InvalidPlatformStyleError),
     Io(std::io::Error),
     ParseInt(std::num::ParseIntError),
+    SerdeJson(serde_json::Error),
     UnimplementedClass(UnimplementedClassError),
     UnimplementedVersion(UnimplementedVersionError),
     UnimplementedImportExport,
@@ -0,0 +1,813 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::BffResult;
+use crate::error::{Error, InvalidGltfError};
+use crate::helpers::{KeyLinearTpl, KeyTgtTpl, KeyframeValue, KeyframerInterpolationType};
+use crate::traits::Artifact;
+
+pub const COMPONENT_TYPE_BYTE: u32 = 5120;
+pub const COMPONENT_TYPE_UNSIGNED_BYTE: u32 = 5121;
+pub const COMPONENT_TYPE_SHORT: u32 = 5122;
+pub const COMPONENT_TYPE_UNSIGNED_SHORT: u32 = 5123;
+pub const COMPONENT_TYPE_UNSIGNED_INT: u32 = 5125;
+pub const COMPONENT_TYPE_FLOAT: u32 = 5126;
+
+pub const TARGET_ARRAY_BUFFER: u32 = 34962;
+pub const TARGET_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+pub const MODE_LINES: u32 = 1;
+pub const MODE_LINE_STRIP: u32 = 3;
+pub const MODE_TRIANGLES: u32 = 4;
+
+pub const KHR_LIGHTS_PUNCTUAL: &str = "KHR_lights_punctual";
+
+// The name of the artifact holding the binary buffer referenced by the glTF document
+pub const GLTF_BUFFER_ARTIFACT_NAME: &str = "buffer";
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Asset {
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Scene {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Node {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mesh: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skin: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<[f32; 16]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation: Option<[f32; 3]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<[f32; 4]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<[f32; 3]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extras: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Primitive {
+    pub attributes: BTreeMap<String, usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indices: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extras: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Mesh {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub primitives: Vec<Primitive>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extras: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Material {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extras: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Skin {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inverse_bind_matrices: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skeleton: Option<usize>,
+    pub joints: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CameraPerspective {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aspect_ratio: Option<f32>,
+    pub yfov: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zfar: Option<f32>,
+    pub znear: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Camera {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub camera_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub perspective: Option<CameraPerspective>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extras: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LightType {
+    Directional,
+    #[default]
+    Point,
+    Spot,
+}
+
+// A KHR_lights_punctual light
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Light {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub light_type: LightType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<[f32; 3]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intensity: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extras: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    Step,
+    CubicSpline,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelPath {
+    Translation,
+    Rotation,
+    Scale,
+    Weights,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelTarget {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<usize>,
+    pub path: ChannelPath,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Channel {
+    pub sampler: usize,
+    pub target: ChannelTarget,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AnimationSampler {
+    pub input: usize,
+    #[serde(default)]
+    pub interpolation: Interpolation,
+    pub output: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Animation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub channels: Vec<Channel>,
+    pub samplers: Vec<AnimationSampler>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extras: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AccessorType {
+    #[default]
+    Scalar,
+    Vec2,
+    Vec3,
+    Vec4,
+    Mat4,
+}
+
+impl AccessorType {
+    pub const fn from_component_count(count: usize) -> Option<Self> {
+        match count {
+            1 => Some(Self::Scalar),
+            2 => Some(Self::Vec2),
+            3 => Some(Self::Vec3),
+            4 => Some(Self::Vec4),
+            16 => Some(Self::Mat4),
+            _ => None,
+        }
+    }
+
+    pub const fn component_count(self) -> usize {
+        match self {
+            Self::Scalar => 1,
+            Self::Vec2 => 2,
+            Self::Vec3 => 3,
+            Self::Vec4 => 4,
+            Self::Mat4 => 16,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Accessor {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_view: Option<usize>,
+    #[serde(default)]
+    pub byte_offset: usize,
+    pub component_type: u32,
+    #[serde(default)]
+    pub normalized: bool,
+    pub count: usize,
+    #[serde(rename = "type")]
+    pub accessor_type: AccessorType,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub min: Vec<f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub max: Vec<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BufferView {
+    pub buffer: usize,
+    #[serde(default)]
+    pub byte_offset: usize,
+    pub byte_length: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub byte_stride: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Buffer {
+    pub byte_length: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Gltf {
+    pub asset: Asset,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions_used: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scene: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scenes: Vec<Scene>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<Node>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub meshes: Vec<Mesh>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub materials: Vec<Material>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skins: Vec<Skin>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cameras: Vec<Camera>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub animations: Vec<Animation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accessors: Vec<Accessor>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub buffer_views: Vec<BufferView>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub buffers: Vec<Buffer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extras: Option<Value>,
+}
+
+pub trait GltfComponent: Copy {
+    const COMPONENT_TYPE: u32;
+
+    fn write_le(self, buffer: &mut Vec<u8>);
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! gltf_component {
+    ($($ty:ty => $component_type:expr),* $(,)?) => {
+        $(
+            impl GltfComponent for $ty {
+                const COMPONENT_TYPE: u32 = $component_type;
+
+                fn write_le(self, buffer: &mut Vec<u8>) {
+                    buffer.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn to_f64(self) -> f64 {
+                    self.into()
+                }
+            }
+        )*
+    };
+}
+
+gltf_component! {
+    i8 => COMPONENT_TYPE_BYTE,
+    u8 => COMPONENT_TYPE_UNSIGNED_BYTE,
+    i16 => COMPONENT_TYPE_SHORT,
+    u16 => COMPONENT_TYPE_UNSIGNED_SHORT,
+    u32 => COMPONENT_TYPE_UNSIGNED_INT,
+    f32 => COMPONENT_TYPE_FLOAT,
+}
+
+// Accumulates a glTF document and its single binary buffer
+pub struct GltfBuilder {
+    pub gltf: Gltf,
+    buffer: Vec<u8>,
+    lights: Vec<Light>,
+}
+
+impl Default for GltfBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GltfBuilder {
+    pub fn new() -> Self {
+        Self {
+            gltf: Gltf {
+                asset: Asset {
+                    version: "2.0".to_owned(),
+                    generator: Some(format!("bff {}", env!("CARGO_PKG_VERSION"))),
+                },
+                ..Default::default()
+            },
+            buffer: Vec::new(),
+            lights: Vec::new(),
+        }
+    }
+
+    pub fn push_accessor<T: GltfComponent, const N: usize>(
+        &mut self,
+        data: &[[T; N]],
+        target: Option<u32>,
+        normalized: bool,
+    ) -> usize {
+        let accessor_type = AccessorType::from_component_count(N)
+            .expect("glTF accessors have 1, 2, 3, 4, or 16 components");
+
+        self.buffer.resize(self.buffer.len().next_multiple_of(4), 0);
+        let byte_offset = self.buffer.len();
+
+        let mut min = [f64::INFINITY; N];
+        let mut max = [f64::NEG_INFINITY; N];
+        for element in data {
+            for (i, component) in element.iter().enumerate() {
+                component.write_le(&mut self.buffer);
+                min[i] = min[i].min(component.to_f64());
+                max[i] = max[i].max(component.to_f64());
+            }
+        }
+
+        let buffer_view = self.gltf.buffer_views.len();
+        self.gltf.buffer_views.push(BufferView {
+            buffer: 0,
+            byte_offset,
+            byte_length: self.buffer.len() - byte_offset,
+            byte_stride: None,
+            target,
+        });
+
+        let (min, max) = if data.is_empty() {
+            (Vec::new(), Vec::new())
+        } else {
+            (min.to_vec(), max.to_vec())
+        };
+
+        let accessor = self.gltf.accessors.len();
+        self.gltf.accessors.push(Accessor {
+            buffer_view: Some(buffer_view),
+            byte_offset: 0,
+            component_type: T::COMPONENT_TYPE,
+            normalized,
+            count: data.len(),
+            accessor_type,
+            min,
+            max,
+        });
+        accessor
+    }
+
+    pub fn push_node(&mut self, node: Node) -> usize {
+        self.gltf.nodes.push(node);
+        self.gltf.nodes.len() - 1
+    }
+
+    pub fn push_mesh(&mut self, mesh: Mesh) -> usize {
+        self.gltf.meshes.push(mesh);
+        self.gltf.meshes.len() - 1
+    }
+
+    pub fn push_material(&mut self, material: Material) -> usize {
+        self.gltf.materials.push(material);
+        self.gltf.materials.len() - 1
+    }
+
+    pub fn push_skin(&mut self, skin: Skin) -> usize {
+        self.gltf.skins.push(skin);
+        self.gltf.skins.len() - 1
+    }
+
+    pub fn push_camera(&mut self, camera: Camera) -> usize {
+        self.gltf.cameras.push(camera);
+        self.gltf.cameras.len() - 1
+    }
+
+    // Lights are only declared by the KHR_lights_punctual extension so they are attached to their
+    // node as they are pushed
+    pub fn push_light(&mut self, node: usize, light: Light) -> usize {
+        self.lights.push(light);
+        let light = self.lights.len() - 1;
+        self.gltf.nodes[node].extensions = Some(json!({ KHR_LIGHTS_PUNCTUAL: { "light": light } }));
+        light
+    }
+
+    pub fn push_animation(&mut self, animation: Animation) -> usize {
+        self.gltf.animations.push(animation);
+        self.gltf.animations.len() - 1
+    }
+
+    // Add a sampler and a channel driving the target node. Cubic spline outputs hold an
+    // in-tangent, value, and out-tangent per input.
+    pub fn push_animation_channel<const N: usize>(
+        &mut self,
+        animation: &mut Animation,
+        target: ChannelTarget,
+        interpolation: Interpolation,
+        times: &[f32],
+        outputs: &[[f32; N]],
+    ) {
+        if times.is_empty() {
+            return;
+        }
+        let times: Vec<[f32; 1]> = times.iter().map(|time| [*time]).collect();
+        let input = self.push_accessor(&times, None, false);
+        let output = self.push_accessor(outputs, None, false);
+        animation.samplers.push(AnimationSampler {
+            input,
+            interpolation,
+            output,
+        });
+        animation.channels.push(Channel {
+            sampler: animation.samplers.len() - 1,
+            target,
+        });
+    }
+
+    // Serialize the document as gltf and bin artifacts. glTF buffers can't be empty so a document
+    // without any binary data has no buffer and no bin artifact.
+    pub fn into_artifacts(
+        mut self,
+        gltf_artifact_name: &str,
+    ) -> BffResult<HashMap<OsString, Artifact>> {
+        if !self.buffer.is_empty() {
+            self.gltf.buffers.push(Buffer {
+                byte_length: self.buffer.len(),
+                uri: Some(format!("{GLTF_BUFFER_ARTIFACT_NAME}.bin")),
+            });
+        }
+        if self.gltf.scenes.is_empty() {
+            let roots = (0..self.gltf.nodes.len())
+                .filter(|i| !self.gltf.nodes.iter().any(|n| n.children.contains(i)))
+                .collect();
+            self.gltf.scenes.push(Scene {
+                name: None,
+                nodes: roots,
+            });
+        }
+        self.gltf.scene.get_or_insert(0);
+        if !self.lights.is_empty() {
+            self.gltf
+                .extensions_used
+                .push(KHR_LIGHTS_PUNCTUAL.to_owned());
+            self.gltf.extensions = Some(json!({ KHR_LIGHTS_PUNCTUAL: { "lights": self.lights } }));
+        }
+
+        let json = serde_json::to_string_pretty(&self.gltf)?;
+
+        let mut artifacts = HashMap::new();
+        artifacts.insert(OsString::from(gltf_artifact_name), Artifact::Gltf(json));
+        if !self.buffer.is_empty() {
+            artifacts.insert(
+                OsString::from(GLTF_BUFFER_ARTIFACT_NAME),
+                Artifact::Binary(self.buffer),
+            );
+        }
+        Ok(artifacts)
+    }
+}
+
+impl KeyframerInterpolationType {
+    pub const fn gltf_interpolation(&self) -> Interpolation {
+        match self {
+            Self::Smooth => Interpolation::CubicSpline,
+            Self::Square => Interpolation::Step,
+            _ => Interpolation::Linear,
+        }
+    }
+}
+
+// Tangents are relative to the adjacent segment so they are rescaled to the per-second
+// derivatives glTF expects
+pub fn tgt_keyframes<T, const N: usize>(
+    keyframes: &[KeyTgtTpl<T>],
+    interpolation: Interpolation,
+) -> (Vec<f32>, Vec<[f32; N]>)
+where
+    T: KeyframeValue<Decoded = [f32; N]>,
+{
+    let times: Vec<f32> = keyframes.iter().map(KeyTgtTpl::time).collect();
+    if interpolation != Interpolation::CubicSpline {
+        let outputs = keyframes.iter().map(|key| key.value().decode()).collect();
+        return (times, outputs);
+    }
+
+    let scale = |tangent: [f32; N], duration: Option<f32>| {
+        duration
+            .filter(|duration| *duration > 0.)
+            .map_or([0.; N], |duration| tangent.map(|x| x / duration))
+    };
+    let outputs = keyframes
+        .iter()
+        .enumerate()
+        .flat_map(|(i, key)| {
+            let duration_in = i.checked_sub(1).map(|previous| times[i] - times[previous]);
+            let duration_out = times.get(i + 1).map(|next| next - times[i]);
+            [
+                scale(key.tangent_in().decode(), duration_in),
+                key.value().decode(),
+                scale(key.tangent_out().decode(), duration_out),
+            ]
+        })
+        .collect();
+    (times, outputs)
+}
+
+pub fn linear_keyframes<T, const N: usize>(
+    keyframes: &[KeyLinearTpl<T>],
+) -> (Vec<f32>, Vec<[f32; N]>)
+where
+    T: KeyframeValue<Decoded = [f32; N]>,
+{
+    keyframes
+        .iter()
+        .map(|key| (key.time(), key.value().decode()))
+        .unzip()
+}
+
+fn invalid_gltf<S: Into<String>>(reason: S) -> Error {
+    InvalidGltfError::new(reason.into()).into()
+}
+
+// A parsed glTF document along with the buffers it references
+pub struct GltfDocument<'a> {
+    pub gltf: Gltf,
+    buffers: Vec<&'a [u8]>,
+}
+
+impl<'a> GltfDocument<'a> {
+    // Buffers are resolved by the file stem of their uri against the artifacts
+    pub fn from_artifacts(
+        artifacts: &'a HashMap<OsString, Artifact>,
+        gltf_artifact_name: &str,
+    ) -> BffResult<Self> {
+        let Artifact::Gltf(json) = artifacts
+            .get(&OsString::from(gltf_artifact_name))
+            .ok_or(Error::ImportBadArtifact)?
+        else {
+            return Err(Error::ImportBadArtifact);
+        };
+
+        let gltf: Gltf = serde_json::from_str(json)?;
+
+        let buffers = gltf
+            .buffers
+            .iter()
+            .map(|buffer| {
+                let uri = buffer
+                    .uri
+                    .as_deref()
+                    .ok_or_else(|| invalid_gltf("buffers without a uri are not supported"))?;
+                let stem = Path::new(uri)
+                    .file_stem()
+                    .ok_or_else(|| invalid_gltf(format!("buffer uri `{uri}` has no file stem")))?;
+                match artifacts.get(stem) {
+                    Some(Artifact::Binary(data)) if data.len() >= buffer.byte_length => {
+                        Ok(data.as_slice())
+                    }
+                    Some(Artifact::Binary(_)) => Err(invalid_gltf(format!(
+                        "buffer `{uri}` is shorter than its byteLength"
+                    ))),
+                    _ => Err(invalid_gltf(format!("missing buffer `{uri}`"))),
+                }
+            })
+            .collect::<BffResult<Vec<_>>>()?;
+
+        Ok(Self { gltf, buffers })
+    }
+
+    pub fn accessor(&self, index: usize) -> BffResult<&Accessor> {
+        self.gltf
+            .accessors
+            .get(index)
+            .ok_or_else(|| invalid_gltf(format!("accessor {index} does not exist")))
+    }
+
+    // Read every element of an accessor as f32 components, applying normalization
+    pub fn read_accessor(&self, index: usize) -> BffResult<Vec<Vec<f32>>> {
+        Ok(self
+            .read_accessor_f64(index)?
+            .into_iter()
+            .map(|element| element.into_iter().map(|c| c as f32).collect())
+            .collect())
+    }
+
+    fn read_accessor_f64(&self, index: usize) -> BffResult<Vec<Vec<f64>>> {
+        let accessor = self.accessor(index)?;
+        let components = accessor.accessor_type.component_count();
+
+        // Accessors without a buffer view are only useful with sparse storage which isn't
+        // supported, and their count is not backed by any data
+        let buffer_view_index = accessor
+            .buffer_view
+            .ok_or_else(|| invalid_gltf(format!("accessor {index} has no buffer view")))?;
+        let buffer_view = self
+            .gltf
+            .buffer_views
+            .get(buffer_view_index)
+            .ok_or_else(|| {
+                invalid_gltf(format!("buffer view {buffer_view_index} does not exist"))
+            })?;
+        let buffer = self
+            .buffers
+            .get(buffer_view.buffer)
+            .ok_or_else(|| invalid_gltf(format!("buffer {} does not exist", buffer_view.buffer)))?;
+
+        let component_size = match accessor.component_type {
+            COMPONENT_TYPE_BYTE | COMPONENT_TYPE_UNSIGNED_BYTE => 1,
+            COMPONENT_TYPE_SHORT | COMPONENT_TYPE_UNSIGNED_SHORT => 2,
+            COMPONENT_TYPE_UNSIGNED_INT | COMPONENT_TYPE_FLOAT => 4,
+            component_type => {
+                return Err(invalid_gltf(format!(
+                    "unsupported component type {component_type}"
+                )));
+            }
+        };
+        let element_size = component_size * components;
+        let stride = buffer_view.byte_stride.unwrap_or(element_size);
+        // Elements may not overlap so the count is bounded by the buffer length
+        if stride < element_size {
+            return Err(invalid_gltf(format!(
+                "buffer view {buffer_view_index} has a stride of {stride} bytes but accessor {index} elements are {element_size} bytes"
+            )));
+        }
+
+        let out_of_range = || {
+            invalid_gltf(format!(
+                "accessor {index} reads past the end of its buffer view"
+            ))
+        };
+        let base = buffer_view
+            .byte_offset
+            .checked_add(accessor.byte_offset)
+            .ok_or_else(out_of_range)?;
+        let view_end = buffer_view
+            .byte_offset
+            .checked_add(buffer_view.byte_length)
+            .ok_or_else(out_of_range)?;
+        if accessor.count > 0 {
+            let end = stride
+                .checked_mul(accessor.count - 1)
+                .and_then(|offset| offset.checked_add(base))
+                .and_then(|offset| offset.checked_add(element_size))
+                .ok_or_else(out_of_range)?;
+            if end > view_end || end > buffer.len() {
+                return Err(out_of_range());
+            }
+        }
+
+        let normalized = accessor.normalized;
+        let component_type = accessor.component_type;
+        let read_component = |offset: usize| -> f64 {
+            let bytes = &buffer[offset..offset + component_size];
+            match component_type {
+                COMPONENT_TYPE_BYTE => {
+                    let value = f64::from(bytes[0] as i8);
+                    if normalized {
+                        (value / 127.).max(-1.)
+                    } else {
+                        value
+                    }
+                }
+                COMPONENT_TYPE_UNSIGNED_BYTE => {
+                    let value = f64::from(bytes[0]);
+                    if normalized { value / 255. } else { value }
+                }
+                COMPONENT_TYPE_SHORT => {
+                    let value = f64::from(i16::from_le_bytes([bytes[0], bytes[1]]));
+                    if normalized {
+                        (value / 32767.).max(-1.)
+                    } else {
+                        value
+                    }
+                }
+                COMPONENT_TYPE_UNSIGNED_SHORT => {
+                    let value = f64::from(u16::from_le_bytes([bytes[0], bytes[1]]));
+                    if normalized { value / 65535. } else { value }
+                }
+                COMPONENT_TYPE_UNSIGNED_INT => {
+                    f64::from(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                }
+                _ => f64::from(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+            }
+        };
+
+        Ok((0..accessor.count)
+            .map(|i| {
+                let element = base + i * stride;
+                (0..components)
+                    .map(|c| read_component(element + c * component_size))
+                    .collect()
+            })
+            .collect())
+    }
+
+    // Read a scalar unsigned integer accessor such as primitive indices
+    pub fn read_indices(&self, index: usize) -> BffResult<Vec<u32>> {
+        let accessor = self.accessor(index)?;
+        let unsigned_integer = matches!(
+            accessor.component_type,
+            COMPONENT_TYPE_UNSIGNED_BYTE
+                | COMPONENT_TYPE_UNSIGNED_SHORT
+                | COMPONENT_TYPE_UNSIGNED_INT
+        );
+        if accessor.accessor_type != AccessorType::Scalar
+            || accessor.normalized
+            || !unsigned_integer
+        {
+            return Err(invalid_gltf(format!(
+                "accessor {index} is not a scalar unsigned integer accessor"
+            )));
+        }
+        Ok(self
+            .read_accessor_f64(index)?
+            .into_iter()
+            .map(|element| element[0] as u32)
+            .collect())
+    }
+}
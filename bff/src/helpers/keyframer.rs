@@ -1,6 +1,17 @@
 use binrw::{BinRead, BinWrite};
 
-use super::{DynArray, NumeratorFloat, Vec, Vec2f, Vec2i16, Vec3f, Vec4f, Vec4i16};
+use super::{
+    DynArray,
+    NumeratorFloat,
+    Quat,
+    Vec,
+    Vec2f,
+    Vec2i16,
+    Vec3f,
+    Vec4f,
+    Vec4i16,
+    quat_slerp,
+};
 use crate::names::Name;
 
 type Key = f32;
@@ -21,6 +32,24 @@ pub struct KeyTgtTpl<T> {
     value: KeyTgtTplValue<T>,
 }
 
+impl<T> KeyTgtTpl<T> {
+    pub const fn time(&self) -> Key {
+        self.time
+    }
+
+    pub const fn value(&self) -> &T {
+        &self.value.value
+    }
+
+    pub const fn tangent_in(&self) -> &T {
+        &self.value.tangent_in
+    }
+
+    pub const fn tangent_out(&self) -> &T {
+        &self.value.tangent_out
+    }
+}
+
 #[derive(..BffStruct)]
 pub struct KeyLinearTpl<T> {
     time: Key,
@@ -29,6 +58,16 @@ pub struct KeyLinearTpl<T> {
     value: T,
 }
 
+impl<T> KeyLinearTpl<T> {
+    pub const fn time(&self) -> Key {
+        self.time
+    }
+
+    pub const fn value(&self) -> &T {
+        &self.value
+    }
+}
+
 #[derive(..BffStruct)]
 #[brw(repr = u16)]
 pub enum KeyframerInterpolationType {
@@ -48,6 +87,16 @@ pub struct KeyframerTpl<TKey> {
     keyframes: DynArray<TKey>,
 }
 
+impl<TKey> KeyframerTpl<TKey> {
+    pub const fn interpolation_type(&self) -> &KeyframerInterpolationType {
+        &self.interpolation_type
+    }
+
+    pub fn keyframes(&self) -> &[TKey] {
+        &self.keyframes
+    }
+}
+
 #[derive(..BffStruct)]
 #[br(bound(for<'a> TKey: BinRead<Args<'a>: Clone + Default> + 'a))]
 #[bw(bound(for<'a> TKey: BinWrite<Args<'a>: Clone + Default> + 'a))]
@@ -55,6 +104,12 @@ pub struct KeyframerNoFlagsTpl<TKey> {
     keyframes: DynArray<TKey>,
 }
 
+impl<TKey> KeyframerNoFlagsTpl<TKey> {
+    pub fn keyframes(&self) -> &[TKey] {
+        &self.keyframes
+    }
+}
+
 #[derive(..BffStruct)]
 pub struct Message {
     message_class: u32,
@@ -64,6 +119,224 @@ pub struct Message {
     message_name: Name,
 }
 
+// Keyframe values that decode to floating point components
+pub trait KeyframeValue {
+    type Decoded: Copy;
+
+    fn decode(&self) -> Self::Decoded;
+}
+
+impl KeyframeValue for f32 {
+    type Decoded = Self;
+
+    fn decode(&self) -> Self::Decoded {
+        *self
+    }
+}
+
+impl KeyframeValue for i16 {
+    type Decoded = f32;
+
+    fn decode(&self) -> Self::Decoded {
+        f32::from(*self)
+    }
+}
+
+impl<const N: usize> KeyframeValue for Vec<N, f32> {
+    type Decoded = Self;
+
+    fn decode(&self) -> Self::Decoded {
+        *self
+    }
+}
+
+impl<const N: usize> KeyframeValue for Vec<N, i16> {
+    type Decoded = Vec<N, f32>;
+
+    fn decode(&self) -> Self::Decoded {
+        self.map(f32::from)
+    }
+}
+
+impl<const N: usize, const DENOMINATOR: usize> KeyframeValue
+    for Vec<N, NumeratorFloat<i16, DENOMINATOR>>
+{
+    type Decoded = Vec<N, f32>;
+
+    fn decode(&self) -> Self::Decoded {
+        self.each_ref().map(|component| **component)
+    }
+}
+
+// Decoded keyframe values that can be blended between two keyframes
+pub trait Interpolate: Copy {
+    fn lerp(self, other: Self, s: f32) -> Self;
+
+    // Cubic Hermite between self and other with tangents already scaled to the segment
+    fn hermite(self, tangent_out: Self, other: Self, tangent_in: Self, s: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn lerp(self, other: Self, s: f32) -> Self {
+        self + (other - self) * s
+    }
+
+    fn hermite(self, tangent_out: Self, other: Self, tangent_in: Self, s: f32) -> Self {
+        let s2 = s * s;
+        let s3 = s2 * s;
+        (2. * s3 - 3. * s2 + 1.) * self
+            + (s3 - 2. * s2 + s) * tangent_out
+            + (-2. * s3 + 3. * s2) * other
+            + (s3 - s2) * tangent_in
+    }
+}
+
+impl<const N: usize> Interpolate for Vec<N, f32> {
+    fn lerp(self, other: Self, s: f32) -> Self {
+        std::array::from_fn(|i| self[i].lerp(other[i], s))
+    }
+
+    fn hermite(self, tangent_out: Self, other: Self, tangent_in: Self, s: f32) -> Self {
+        std::array::from_fn(|i| self[i].hermite(tangent_out[i], other[i], tangent_in[i], s))
+    }
+}
+
+trait Keyframe {
+    fn time(&self) -> Key;
+}
+
+impl<T> Keyframe for KeyTgtTpl<T> {
+    fn time(&self) -> Key {
+        self.time
+    }
+}
+
+impl<T> Keyframe for KeyLinearTpl<T> {
+    fn time(&self) -> Key {
+        self.time
+    }
+}
+
+// The keyframes surrounding t and how far t is between them. Times outside of the keyframes
+// clamp to the first or last keyframe.
+fn segment<K: Keyframe>(keyframes: &[K], t: Key) -> Option<(&K, &K, f32)> {
+    let next = keyframes.partition_point(|key| key.time() <= t);
+    let (k0, k1) = match (next.checked_sub(1), keyframes.get(next)) {
+        (Some(previous), Some(k1)) => (&keyframes[previous], k1),
+        (Some(previous), None) => (&keyframes[previous], &keyframes[previous]),
+        (None, Some(k1)) => (k1, k1),
+        (None, None) => return None,
+    };
+    let duration = k1.time() - k0.time();
+    let s = if duration > 0. {
+        (t - k0.time()) / duration
+    } else {
+        0.
+    };
+    Some((k0, k1, s))
+}
+
+// The last keyframe at or before t, or the first keyframe if t precedes all of them
+fn step<K: Keyframe>(keyframes: &[K], t: Key) -> Option<&K> {
+    let next = keyframes.partition_point(|key| key.time() <= t);
+    keyframes.get(next.saturating_sub(1))
+}
+
+fn sample_tgt<T>(
+    keyframes: &[KeyTgtTpl<T>],
+    interpolation_type: &KeyframerInterpolationType,
+    t: Key,
+) -> Option<T::Decoded>
+where
+    T: KeyframeValue<Decoded: Interpolate>,
+{
+    let (k0, k1, s) = segment(keyframes, t)?;
+    let (p0, p1) = (k0.value().decode(), k1.value().decode());
+    Some(match interpolation_type {
+        KeyframerInterpolationType::Smooth => {
+            p0.hermite(k0.tangent_out().decode(), p1, k1.tangent_in().decode(), s)
+        }
+        KeyframerInterpolationType::Square => step(keyframes, t)?.value().decode(),
+        _ => p0.lerp(p1, s),
+    })
+}
+
+fn sample_linear<T>(
+    keyframes: &[KeyLinearTpl<T>],
+    interpolation_type: &KeyframerInterpolationType,
+    t: Key,
+) -> Option<T::Decoded>
+where
+    T: KeyframeValue<Decoded: Interpolate>,
+{
+    if matches!(interpolation_type, KeyframerInterpolationType::Square) {
+        return step(keyframes, t).map(|key| key.value().decode());
+    }
+    let (k0, k1, s) = segment(keyframes, t)?;
+    Some(k0.value().decode().lerp(k1.value().decode(), s))
+}
+
+impl<T> KeyframerTpl<KeyTgtTpl<T>>
+where
+    T: KeyframeValue<Decoded: Interpolate>,
+{
+    pub fn sample(&self, t: Key) -> Option<T::Decoded> {
+        sample_tgt(&self.keyframes, &self.interpolation_type, t)
+    }
+}
+
+// Linear keyframes have no tangents so Smooth falls back to Linear
+impl<T> KeyframerTpl<KeyLinearTpl<T>>
+where
+    T: KeyframeValue<Decoded: Interpolate>,
+{
+    pub fn sample(&self, t: Key) -> Option<T::Decoded> {
+        sample_linear(&self.keyframes, &self.interpolation_type, t)
+    }
+}
+
+// Sample a run of Euler bezier rotation keys, such as the frames of one animation modifier.
+// Euler angles in radians.
+pub fn sample_bezier_rot(keyframes: &[KeyBezierRot], t: Key) -> Option<Vec3f> {
+    sample_tgt(keyframes, &KeyframerInterpolationType::Smooth, t)
+}
+
+// The rotation keyframers carry no interpolation flags, their key types imply it
+impl KeyframerBezierRot {
+    pub const fn interpolation_type(&self) -> &KeyframerInterpolationType {
+        &KeyframerInterpolationType::Smooth
+    }
+
+    pub fn sample(&self, t: Key) -> Option<Vec3f> {
+        sample_bezier_rot(&self.keyframes, t)
+    }
+}
+
+impl KeyframerRot {
+    pub const fn interpolation_type(&self) -> &KeyframerInterpolationType {
+        &KeyframerInterpolationType::Linear
+    }
+
+    pub fn sample(&self, t: Key) -> Option<Quat> {
+        let (k0, k1, s) = segment(&self.keyframes, t)?;
+        Some(quat_slerp(k0.value().decode(), k1.value().decode(), s))
+    }
+}
+
+impl<T> KeyframerNoFlagsTpl<KeyLinearTpl<T>> {
+    // Step semantics, the value of the last keyframe at or before t
+    pub fn step(&self, t: Key) -> Option<&T> {
+        step(&self.keyframes, t).map(KeyLinearTpl::value)
+    }
+
+    // Keyframes with start <= time < end, for firing discrete keys such as messages once
+    pub fn keyframes_between(&self, start: Key, end: Key) -> &[KeyLinearTpl<T>] {
+        let first = self.keyframes.partition_point(|key| key.time < start);
+        let last = self.keyframes.partition_point(|key| key.time < end);
+        self.keyframes.get(first..last).unwrap_or_default()
+    }
+}
+
 pub type Vec3Comp = Vec<3, NumeratorFloat<i16, 4096>>;
 pub type QuatComp = Vec<4, NumeratorFloat<i16, 2000>>;
 
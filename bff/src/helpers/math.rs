@@ -91,6 +91,12 @@ pub struct RangeFirstLast<T = u16> {
     inner: RangeInclusive<T>,
 }
 
+impl<T> From<RangeInclusive<T>> for RangeFirstLast<T> {
+    fn from(inner: RangeInclusive<T>) -> Self {
+        Self { inner }
+    }
+}
+
 // Range whose first element is first and contains size elements. [first, first + size).
 #[binrw]
 #[derive(Debug, Serialize, Deref, DerefMut, Deserialize, ReferencedNames, JsonSchema)]
@@ -113,6 +119,12 @@ pub struct RangeBeginSize<T = u16> {
     inner: Range<T>,
 }
 
+impl<T> From<Range<T>> for RangeBeginSize<T> {
+    fn from(inner: Range<T>) -> Self {
+        Self { inner }
+    }
+}
+
 #[derive(..BffStruct)]
 pub struct Sphere {
     pub center: Vec3f,
@@ -161,3 +173,189 @@ pub struct Rect<T = i32> {
     pub top_left: Vec2<T>,
     pub bottom_right: Vec2<T>,
 }
+
+pub const MAT4F_IDENTITY: Mat4f = [
+    [1., 0., 0., 0.],
+    [0., 1., 0., 0.],
+    [0., 0., 1., 0.],
+    [0., 0., 0., 1.],
+];
+
+// Matrices use the row vector convention with the translation in the last row, so a * b
+// applies a then b. Flattening the rows gives the column-major layout glTF expects.
+pub fn mat4f_mul(a: &Mat4f, b: &Mat4f) -> Mat4f {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..4).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+pub fn mat4f_inverse(m: &Mat4f) -> Option<Mat4f> {
+    let mut a = *m;
+    let mut inverse = MAT4F_IDENTITY;
+    for column in 0..4 {
+        let pivot =
+            (column..4).max_by(|x, y| a[*x][column].abs().total_cmp(&a[*y][column].abs()))?;
+        if a[pivot][column].abs() <= f32::EPSILON {
+            return None;
+        }
+        a.swap(column, pivot);
+        inverse.swap(column, pivot);
+
+        let scale = a[column][column];
+        a[column] = a[column].map(|x| x / scale);
+        inverse[column] = inverse[column].map(|x| x / scale);
+
+        for row in 0..4 {
+            if row != column {
+                let factor = a[row][column];
+                let (a_pivot, inverse_pivot) = (a[column], inverse[column]);
+                for (x, p) in a[row].iter_mut().zip(a_pivot) {
+                    *x -= factor * p;
+                }
+                for (x, p) in inverse[row].iter_mut().zip(inverse_pivot) {
+                    *x -= factor * p;
+                }
+            }
+        }
+    }
+    Some(inverse)
+}
+
+pub fn mat4f_flatten(m: &Mat4f) -> [f32; 16] {
+    std::array::from_fn(|i| m[i / 4][i % 4])
+}
+
+// Split an affine matrix into translation, rotation quaternion (x, y, z, w), and scale
+pub fn mat4f_decompose(m: &Mat4f) -> (Vec3f, Quat, Vec3f) {
+    let translation = [m[3][0], m[3][1], m[3][2]];
+
+    let mut rows = [0, 1, 2].map(|i| [m[i][0], m[i][1], m[i][2]]);
+    let mut scale = rows.map(|[x, y, z]| (x * x + y * y + z * z).sqrt());
+    let [a, b, c] = rows;
+    let determinant = a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+        + a[2] * (b[0] * c[1] - b[1] * c[0]);
+    if determinant < 0. {
+        scale[0] = -scale[0];
+    }
+    for (row, scale) in rows.iter_mut().zip(scale) {
+        if scale != 0. {
+            *row = row.map(|x| x / scale);
+        }
+    }
+
+    // Column vector rotation matrix element (i, j)
+    let r = |i: usize, j: usize| rows[j][i];
+    let trace = r(0, 0) + r(1, 1) + r(2, 2);
+    let rotation = if trace > 0. {
+        let s = (trace + 1.).sqrt() * 2.;
+        [
+            (r(2, 1) - r(1, 2)) / s,
+            (r(0, 2) - r(2, 0)) / s,
+            (r(1, 0) - r(0, 1)) / s,
+            s / 4.,
+        ]
+    } else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+        let s = (1. + r(0, 0) - r(1, 1) - r(2, 2)).sqrt() * 2.;
+        [
+            s / 4.,
+            (r(0, 1) + r(1, 0)) / s,
+            (r(0, 2) + r(2, 0)) / s,
+            (r(2, 1) - r(1, 2)) / s,
+        ]
+    } else if r(1, 1) > r(2, 2) {
+        let s = (1. + r(1, 1) - r(0, 0) - r(2, 2)).sqrt() * 2.;
+        [
+            (r(0, 1) + r(1, 0)) / s,
+            s / 4.,
+            (r(1, 2) + r(2, 1)) / s,
+            (r(0, 2) - r(2, 0)) / s,
+        ]
+    } else {
+        let s = (1. + r(2, 2) - r(0, 0) - r(1, 1)).sqrt() * 2.;
+        [
+            (r(0, 2) + r(2, 0)) / s,
+            (r(1, 2) + r(2, 1)) / s,
+            s / 4.,
+            (r(1, 0) - r(0, 1)) / s,
+        ]
+    };
+
+    (translation, quat_normalize(rotation), scale)
+}
+
+pub fn quat_normalize(q: Quat) -> Quat {
+    let length = q.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if length > 0. {
+        q.map(|x| x / length)
+    } else {
+        [0., 0., 0., 1.]
+    }
+}
+
+// Build a row vector convention matrix from translation, rotation quaternion (x, y, z, w), and scale
+pub fn mat4f_compose(translation: Vec3f, rotation: Quat, scale: Vec3f) -> Mat4f {
+    let [x, y, z, w] = quat_normalize(rotation);
+    let rows = [
+        [
+            1. - 2. * (y * y + z * z),
+            2. * (x * y + z * w),
+            2. * (x * z - y * w),
+        ],
+        [
+            2. * (x * y - z * w),
+            1. - 2. * (x * x + z * z),
+            2. * (y * z + x * w),
+        ],
+        [
+            2. * (x * z + y * w),
+            2. * (y * z - x * w),
+            1. - 2. * (x * x + y * y),
+        ],
+    ];
+    let [a, b, c] = [0, 1, 2].map(|i| rows[i].map(|v| v * scale[i]));
+    [
+        [a[0], a[1], a[2], 0.],
+        [b[0], b[1], b[2], 0.],
+        [c[0], c[1], c[2], 0.],
+        [translation[0], translation[1], translation[2], 1.],
+    ]
+}
+
+// Hamilton product of two (x, y, z, w) quaternions
+pub fn quat_mul(a: Quat, b: Quat) -> Quat {
+    let [ax, ay, az, aw] = a;
+    let [bx, by, bz, bw] = b;
+    [
+        aw * bx + ax * bw + ay * bz - az * by,
+        aw * by - ax * bz + ay * bw + az * bx,
+        aw * bz + ax * by - ay * bx + az * bw,
+        aw * bw - ax * bx - ay * by - az * bz,
+    ]
+}
+
+// Euler angles in radians applied in X, Y, Z order
+pub fn quat_from_euler(euler: Vec3f) -> Quat {
+    let [x, y, z] = euler.map(|angle| ((angle / 2.).sin(), (angle / 2.).cos()));
+    let qx = [x.0, 0., 0., x.1];
+    let qy = [0., y.0, 0., y.1];
+    let qz = [0., 0., z.0, z.1];
+    quat_mul(qz, quat_mul(qy, qx))
+}
+
+// Spherical interpolation along the shortest arc, falling back to a normalized lerp when the
+// quaternions are nearly parallel
+pub fn quat_slerp(a: Quat, b: Quat, s: f32) -> Quat {
+    let a = quat_normalize(a);
+    let mut b = quat_normalize(b);
+    let mut cos = a.iter().zip(b.iter()).map(|(a, b)| a * b).sum::<f32>();
+    if cos < 0. {
+        b = b.map(|x| -x);
+        cos = -cos;
+    }
+    let (wa, wb) = if cos > 0.9995 {
+        (1. - s, s)
+    } else {
+        let angle = cos.acos();
+        let sin = angle.sin();
+        (((1. - s) * angle).sin() / sin, (s * angle).sin() / sin)
+    };
+    quat_normalize(std::array::from_fn(|i| wa * a[i] + wb * b[i]))
+}
@@ -3,12 +3,16 @@ use std::io::{self, Read, Seek, SeekFrom, Write};
 use binrw::BinResult;
 
 mod dynarray;
+pub mod gltf;
 mod keyframer;
 mod link_header;
 mod map;
 mod math;
+pub mod obj;
 mod option;
+pub mod spline;
 mod strings;
+pub mod texture;
 
 pub const fn calculate_padding(position: usize, alignment: usize) -> usize {
     position.next_multiple_of(alignment) - position
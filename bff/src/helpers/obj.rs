@@ -0,0 +1,118 @@
+use std::fmt::Write as _;
+
+use crate::helpers::{Mat4f, Vec3f};
+
+// Builds a Wavefront OBJ document. Vertex colors use the common `v x y z r g b` extension.
+#[derive(Default)]
+pub struct ObjWriter {
+    obj: String,
+    vertex_count: usize,
+}
+
+// The corners of the box between min and max, and the corner pairs making up its edges
+fn box_corners(min: Vec3f, max: Vec3f) -> [Vec3f; 8] {
+    std::array::from_fn(|i| {
+        [
+            if i & 1 == 0 { min[0] } else { max[0] },
+            if i & 2 == 0 { min[1] } else { max[1] },
+            if i & 4 == 0 { min[2] } else { max[2] },
+        ]
+    })
+}
+
+const BOX_EDGES: [[usize; 2]; 12] = [
+    [0, 1],
+    [2, 3],
+    [4, 5],
+    [6, 7],
+    [0, 2],
+    [1, 3],
+    [4, 6],
+    [5, 7],
+    [0, 4],
+    [1, 5],
+    [2, 6],
+    [3, 7],
+];
+
+impl ObjWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn object(&mut self, name: &str) {
+        let _ = writeln!(self.obj, "o {name}");
+    }
+
+    pub fn comment(&mut self, comment: &str) {
+        let _ = writeln!(self.obj, "# {comment}");
+    }
+
+    pub fn group(&mut self, name: &str) {
+        let _ = writeln!(self.obj, "g {name}");
+    }
+
+    // Returns the 1-based index of the vertex
+    pub fn vertex(&mut self, [x, y, z]: Vec3f) -> usize {
+        let _ = writeln!(self.obj, "v {x} {y} {z}");
+        self.vertex_count += 1;
+        self.vertex_count
+    }
+
+    pub fn colored_vertex(&mut self, [x, y, z]: Vec3f, [r, g, b]: Vec3f) -> usize {
+        let _ = writeln!(self.obj, "v {x} {y} {z} {r} {g} {b}");
+        self.vertex_count += 1;
+        self.vertex_count
+    }
+
+    pub fn face(&mut self, [a, b, c]: [usize; 3]) {
+        let _ = writeln!(self.obj, "f {a} {b} {c}");
+    }
+
+    pub fn line(&mut self, indices: &[usize]) {
+        self.obj.push('l');
+        for index in indices {
+            let _ = write!(self.obj, " {index}");
+        }
+        self.obj.push('\n');
+    }
+
+    pub fn wireframe_box(&mut self, min: Vec3f, max: Vec3f) {
+        self.transformed_wireframe_box(min, max, None);
+    }
+
+    // Corners are transformed as row vectors
+    pub fn transformed_wireframe_box(&mut self, min: Vec3f, max: Vec3f, transform: Option<&Mat4f>) {
+        let corners = box_corners(min, max).map(|corner| {
+            let corner = match transform {
+                Some(m) => std::array::from_fn(|j| {
+                    corner[0] * m[0][j] + corner[1] * m[1][j] + corner[2] * m[2][j] + m[3][j]
+                }),
+                None => corner,
+            };
+            self.vertex(corner)
+        });
+        for [a, b] in BOX_EDGES {
+            self.line(&[corners[a], corners[b]]);
+        }
+    }
+
+    pub fn finish(self) -> String {
+        self.obj
+    }
+}
+
+// A stable, well spread color for a small integer key such as a surface type
+pub fn key_color(key: u32) -> Vec3f {
+    // Golden ratio hue stepping
+    let hue = (key as f32 * 0.618_034).fract() * 6.;
+    let x = 1. - (hue % 2. - 1.).abs();
+    match hue as u32 {
+        0 => [1., x, 0.],
+        1 => [x, 1., 0.],
+        2 => [0., 1., x],
+        3 => [0., x, 1.],
+        4 => [x, 0., 1.],
+        _ => [1., 0., x],
+    }
+}
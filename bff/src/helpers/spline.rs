@@ -0,0 +1,194 @@
+use std::collections::BTreeSet;
+
+use crate::BffResult;
+use crate::error::Error;
+use crate::helpers::obj::ObjWriter;
+use crate::helpers::{Vec3f, Vec4f};
+
+pub const SUBDIVISION_COUNT: usize = 8;
+
+// Segments are cubic Hermite curves between two points of the spline's point
+// pool, the tangents are also stored in the pool
+pub fn hermite(p0: Vec3f, t0: Vec3f, p1: Vec3f, t1: Vec3f, s: f32) -> Vec3f {
+    let s2 = s * s;
+    let s3 = s2 * s;
+    let h00 = 2. * s3 - 3. * s2 + 1.;
+    let h10 = s3 - 2. * s2 + s;
+    let h01 = -2. * s3 + 3. * s2;
+    let h11 = s3 - s2;
+    std::array::from_fn(|i| h00 * p0[i] + h10 * t0[i] + h01 * p1[i] + h11 * t1[i])
+}
+
+fn distance(a: Vec3f, b: Vec3f) -> f32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f32>()
+        .sqrt()
+}
+
+fn point(points: &[Vec3f], index: u16) -> BffResult<Vec3f> {
+    points
+        .get(index as usize)
+        .copied()
+        .ok_or(Error::ImportBadArtifact)
+}
+
+// Returns the end points and length of each subdivision of a segment
+pub fn subdivide(
+    points: &[Vec3f],
+    p: [u16; 2],
+    t: [u16; 2],
+) -> BffResult<[([Vec3f; 2], f32); SUBDIVISION_COUNT]> {
+    let p0 = point(points, p[0])?;
+    let p1 = point(points, p[1])?;
+    let t0 = point(points, t[0])?;
+    let t1 = point(points, t[1])?;
+    let sample = |i: usize| hermite(p0, t0, p1, t1, i as f32 / SUBDIVISION_COUNT as f32);
+    Ok(std::array::from_fn(|i| {
+        let (a, b) = (sample(i), sample(i + 1));
+        ([a, b], distance(a, b))
+    }))
+}
+
+// Implemented by the segment types of the spline classes so that they can share
+// the OBJ export and import
+pub trait HermiteSegment {
+    fn point_ids(&self) -> [u16; 2];
+    fn tangent_ids(&self) -> [u16; 2];
+    fn length(&self) -> f32;
+    fn subdivision_vertices(&self) -> impl Iterator<Item = [Vec3f; 2]>;
+    fn set_subdivisions(&mut self, subdivisions: [([Vec3f; 2], f32); SUBDIVISION_COUNT]);
+}
+
+const POINTS_OBJECT_NAME: &str = "points";
+
+// The point pool is the first object so that the vertices keep their indices
+// on import, with the connectivity of the segments as lines. Tangents share the
+// pool but are directions rather than positions, their indices are listed in a
+// comment. The sampled segments follow as separate objects for display only.
+pub fn spline_to_obj<S: HermiteSegment>(points: &[Vec3f], segments: &[S]) -> String {
+    let mut obj = ObjWriter::new();
+    obj.object(POINTS_OBJECT_NAME);
+    let tangents: BTreeSet<_> = segments
+        .iter()
+        .flat_map(|segment| segment.tangent_ids())
+        .collect();
+    let tangents: Vec<_> = tangents
+        .iter()
+        .map(|t| (*t as usize + 1).to_string())
+        .collect();
+    obj.comment(&format!("tangents: {}", tangents.join(" ")));
+    for point in points {
+        obj.vertex(*point);
+    }
+    for segment in segments {
+        obj.line(&segment.point_ids().map(|p| p as usize + 1));
+    }
+
+    for (i, segment) in segments.iter().enumerate() {
+        obj.object(&format!("segment_{i}"));
+        let mut subdivisions = segment.subdivision_vertices().peekable();
+        let mut samples = Vec::new();
+        while let Some([start, end]) = subdivisions.next() {
+            samples.push(obj.vertex(start));
+            if subdivisions.peek().is_none() {
+                samples.push(obj.vertex(end));
+            }
+        }
+        obj.line(&samples);
+    }
+
+    obj.finish()
+}
+
+// Reads the vertices of the points object, whatever else the file contains
+fn obj_to_points(obj: &str, point_count: usize) -> BffResult<Vec<Vec3f>> {
+    let mut in_points = false;
+    let mut points = Vec::with_capacity(point_count);
+    for line in obj.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix("o ") {
+            in_points = name.trim() == POINTS_OBJECT_NAME;
+        } else if in_points && let Some(vertex) = line.strip_prefix("v ") {
+            let mut coordinates = vertex
+                .split_whitespace()
+                .map(|c| c.parse::<f32>().map_err(|_| Error::ImportBadArtifact));
+            let mut next = || coordinates.next().unwrap_or(Err(Error::ImportBadArtifact));
+            points.push([next()?, next()?, next()?]);
+        }
+    }
+
+    if points.len() != point_count {
+        return Err(Error::ImportBadArtifact);
+    }
+
+    Ok(points)
+}
+
+// Assumed to be the center and radius of a sphere around the sampled curve
+fn bounding_sphere(vertices: &[Vec3f]) -> Vec4f {
+    let Some(first) = vertices.first() else {
+        return [0.; 4];
+    };
+    let (min, max) = vertices.iter().fold((*first, *first), |(min, max), v| {
+        (
+            std::array::from_fn(|i| min[i].min(v[i])),
+            std::array::from_fn(|i| max[i].max(v[i])),
+        )
+    });
+    let center: Vec3f = std::array::from_fn(|i| (min[i] + max[i]) / 2.);
+    let radius = vertices
+        .iter()
+        .map(|v| distance(center, *v))
+        .fold(0., f32::max);
+    [center[0], center[1], center[2], radius]
+}
+
+pub struct SplineObj<'a, S> {
+    pub points: &'a mut Vec<Vec3f>,
+    pub segments: &'a mut [S],
+    pub vec: &'a mut Vec4f,
+    pub length: &'a mut f32,
+}
+
+impl<S: HermiteSegment> SplineObj<'_, S> {
+    // Only segments with moved control points are recalculated so that an
+    // unedited spline keeps its original subdivisions and bounds
+    pub fn import(self, obj: &str) -> BffResult<()> {
+        let points = obj_to_points(obj, self.points.len())?;
+
+        let mut subdivisions = Vec::new();
+        for (i, segment) in self.segments.iter().enumerate() {
+            let moved = segment
+                .point_ids()
+                .iter()
+                .chain(segment.tangent_ids().iter())
+                .any(|index| self.points.get(*index as usize) != points.get(*index as usize));
+            if moved {
+                let segment_subdivisions =
+                    subdivide(&points, segment.point_ids(), segment.tangent_ids())?;
+                subdivisions.push((i, segment_subdivisions));
+            }
+        }
+
+        // Nothing below can fail
+        for (i, segment_subdivisions) in &subdivisions {
+            let segment = &mut self.segments[*i];
+            let old_length = segment.length();
+            segment.set_subdivisions(*segment_subdivisions);
+            *self.length += segment.length() - old_length;
+        }
+        if !subdivisions.is_empty() {
+            let vertices: Vec<_> = self
+                .segments
+                .iter()
+                .flat_map(|segment| segment.subdivision_vertices())
+                .flatten()
+                .collect();
+            *self.vec = bounding_sphere(&vertices);
+        }
+        *self.points = points;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,556 @@
+use std::io::Cursor;
+
+use ddsfile::{Caps2, D3DFormat, Dds, NewD3dParams};
+
+use crate::BffResult;
+use crate::error::Error;
+
+// Software decoding and encoding for the texture formats used by Bitmap. Only
+// the top mip of every face goes into the PNG, the rest of the chain is
+// regenerated when importing. Cubemap faces are stacked vertically in DDS
+// order.
+
+type Block = [[u8; 4]; 16];
+
+const fn block_count(width: u32, height: u32) -> usize {
+    width.div_ceil(4) as usize * height.div_ceil(4) as usize
+}
+
+const fn mip_dimension(dimension: u32, level: u32) -> u32 {
+    let dimension = dimension >> level;
+    if dimension == 0 { 1 } else { dimension }
+}
+
+const fn bytes_per_pixel(format: D3DFormat) -> Option<usize> {
+    match format {
+        D3DFormat::A8R8G8B8 | D3DFormat::X8R8G8B8 => Some(4),
+        D3DFormat::R5G6B5 | D3DFormat::A1R5G5B5 | D3DFormat::A4R4G4B4 | D3DFormat::A8L8 => Some(2),
+        D3DFormat::L8 => Some(1),
+        _ => None,
+    }
+}
+
+pub const fn level_size(format: D3DFormat, width: u32, height: u32) -> BffResult<usize> {
+    match format {
+        D3DFormat::DXT1 => Ok(block_count(width, height) * 8),
+        D3DFormat::DXT3 | D3DFormat::DXT5 => Ok(block_count(width, height) * 16),
+        _ => match bytes_per_pixel(format) {
+            Some(bytes_per_pixel) => Ok(width as usize * height as usize * bytes_per_pixel),
+            None => Err(Error::UnimplementedImportExport),
+        },
+    }
+}
+
+fn chain_size(format: D3DFormat, width: u32, height: u32, levels: u32) -> BffResult<usize> {
+    (0..levels.max(1))
+        .map(|level| {
+            level_size(
+                format,
+                mip_dimension(width, level),
+                mip_dimension(height, level),
+            )
+        })
+        .sum()
+}
+
+const fn rgb565_to_rgba(color: u16) -> [u8; 4] {
+    let r = (color >> 11) & 0x1F;
+    let g = (color >> 5) & 0x3F;
+    let b = color & 0x1F;
+    [
+        ((r << 3) | (r >> 2)) as u8,
+        ((g << 2) | (g >> 4)) as u8,
+        ((b << 3) | (b >> 2)) as u8,
+        255,
+    ]
+}
+
+const fn expand(value: u16, bits: u32) -> u8 {
+    let max = (1 << bits) - 1;
+    ((value as u32 * 255 + max / 2) / max) as u8
+}
+
+const fn quantize(value: u8, bits: u32) -> u16 {
+    ((value as u32 * ((1 << bits) - 1) + 127) / 255) as u16
+}
+
+const fn rgba_to_rgb565(color: [u8; 4]) -> u16 {
+    (quantize(color[0], 5) << 11) | (quantize(color[1], 6) << 5) | quantize(color[2], 5)
+}
+
+// Rec. 601 luma
+const fn luminance(color: [u8; 4]) -> u8 {
+    ((color[0] as u32 * 299 + color[1] as u32 * 587 + color[2] as u32 * 114 + 500) / 1000) as u8
+}
+
+fn mix(a: [u8; 4], b: [u8; 4], weight_a: u16, weight_b: u16) -> [u8; 4] {
+    let total = weight_a + weight_b;
+    std::array::from_fn(|i| ((a[i] as u16 * weight_a + b[i] as u16 * weight_b) / total) as u8)
+}
+
+// DXT1 switches to three colors and transparent black when c0 <= c1, DXT3 and
+// DXT5 always use four colors
+fn color_palette(c0: u16, c1: u16, allow_transparent: bool) -> [[u8; 4]; 4] {
+    let p0 = rgb565_to_rgba(c0);
+    let p1 = rgb565_to_rgba(c1);
+    if c0 > c1 || !allow_transparent {
+        [p0, p1, mix(p0, p1, 2, 1), mix(p0, p1, 1, 2)]
+    } else {
+        [p0, p1, mix(p0, p1, 1, 1), [0, 0, 0, 0]]
+    }
+}
+
+fn alpha_palette(a0: u8, a1: u8) -> [u8; 8] {
+    let (a0, a1) = (a0 as u16, a1 as u16);
+    std::array::from_fn(|i| match (i as u16, a0 > a1) {
+        (0, _) => a0 as u8,
+        (1, _) => a1 as u8,
+        (i, true) => (((8 - i) * a0 + (i - 1) * a1) / 7) as u8,
+        (6, false) => 0,
+        (7, false) => 255,
+        (i, false) => (((6 - i) * a0 + (i - 1) * a1) / 5) as u8,
+    })
+}
+
+fn decode_color_block(block: &[u8], allow_transparent: bool) -> Block {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let palette = color_palette(c0, c1, allow_transparent);
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    std::array::from_fn(|i| palette[(indices >> (2 * i)) as usize & 3])
+}
+
+fn decode_alpha_block(block: &[u8]) -> [u8; 16] {
+    let palette = alpha_palette(block[0], block[1]);
+    let indices = block[2..8]
+        .iter()
+        .rev()
+        .fold(0u64, |indices, byte| (indices << 8) | *byte as u64);
+    std::array::from_fn(|i| palette[(indices >> (3 * i)) as usize & 7])
+}
+
+fn decode_explicit_alpha_block(block: &[u8]) -> [u8; 16] {
+    let indices = u64::from_le_bytes(block[..8].try_into().unwrap_or_default());
+    std::array::from_fn(|i| expand(((indices >> (4 * i)) & 0xF) as u16, 4))
+}
+
+fn color_distance(a: [u8; 4], b: [u8; 4]) -> u32 {
+    (0..3)
+        .map(|i| (a[i] as i32 - b[i] as i32).pow(2) as u32)
+        .sum()
+}
+
+fn encode_color_block(pixels: &Block, allow_transparent: bool) -> [u8; 8] {
+    let transparent = allow_transparent && pixels.iter().any(|p| p[3] < 128);
+    let opaque = || pixels.iter().filter(|p| !transparent || p[3] >= 128);
+
+    let Some(first) = opaque().next() else {
+        // Every pixel is transparent
+        return [0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF];
+    };
+    let (min, max) = opaque().fold((*first, *first), |(min, max), p| {
+        (
+            std::array::from_fn(|i| min[i].min(p[i])),
+            std::array::from_fn(|i| max[i].max(p[i])),
+        )
+    });
+
+    let (mut c0, mut c1) = (rgba_to_rgb565(max), rgba_to_rgb565(min));
+    if (transparent && c0 > c1) || (!transparent && c0 < c1) {
+        std::mem::swap(&mut c0, &mut c1);
+    }
+    let palette = color_palette(c0, c1, allow_transparent);
+    let colors = if c0 > c1 || !allow_transparent { 4 } else { 3 };
+
+    let indices = pixels.iter().enumerate().fold(0u32, |indices, (i, p)| {
+        let index = if transparent && p[3] < 128 {
+            3
+        } else {
+            (0..colors)
+                .min_by_key(|j| color_distance(*p, palette[*j]))
+                .unwrap_or_default()
+        };
+        indices | ((index as u32) << (2 * i))
+    });
+
+    let mut block = [0; 8];
+    block[0..2].copy_from_slice(&c0.to_le_bytes());
+    block[2..4].copy_from_slice(&c1.to_le_bytes());
+    block[4..8].copy_from_slice(&indices.to_le_bytes());
+    block
+}
+
+fn encode_alpha_block(pixels: &Block) -> [u8; 8] {
+    let a0 = pixels.iter().map(|p| p[3]).max().unwrap_or_default();
+    let a1 = pixels.iter().map(|p| p[3]).min().unwrap_or_default();
+    let palette = alpha_palette(a0, a1);
+
+    let indices = pixels.iter().enumerate().fold(0u64, |indices, (i, p)| {
+        let index = (0..8)
+            .min_by_key(|j| (palette[*j] as i16 - p[3] as i16).unsigned_abs())
+            .unwrap_or_default();
+        indices | ((index as u64) << (3 * i))
+    });
+
+    let mut block = [a0, a1, 0, 0, 0, 0, 0, 0];
+    block[2..8].copy_from_slice(&indices.to_le_bytes()[..6]);
+    block
+}
+
+fn encode_explicit_alpha_block(pixels: &Block) -> [u8; 8] {
+    pixels
+        .iter()
+        .enumerate()
+        .fold(0u64, |indices, (i, p)| {
+            indices | ((quantize(p[3], 4) as u64) << (4 * i))
+        })
+        .to_le_bytes()
+}
+
+fn decode_pixel(format: D3DFormat, bytes: &[u8]) -> [u8; 4] {
+    let packed = || u16::from_le_bytes([bytes[0], bytes[1]]);
+    match format {
+        D3DFormat::A8R8G8B8 => [bytes[2], bytes[1], bytes[0], bytes[3]],
+        D3DFormat::X8R8G8B8 => [bytes[2], bytes[1], bytes[0], 255],
+        D3DFormat::R5G6B5 => rgb565_to_rgba(packed()),
+        D3DFormat::A1R5G5B5 => {
+            let packed = packed();
+            [
+                expand((packed >> 10) & 0x1F, 5),
+                expand((packed >> 5) & 0x1F, 5),
+                expand(packed & 0x1F, 5),
+                if packed & 0x8000 != 0 { 255 } else { 0 },
+            ]
+        }
+        D3DFormat::A4R4G4B4 => {
+            let packed = packed();
+            [
+                expand((packed >> 8) & 0xF, 4),
+                expand((packed >> 4) & 0xF, 4),
+                expand(packed & 0xF, 4),
+                expand(packed >> 12, 4),
+            ]
+        }
+        D3DFormat::A8L8 => [bytes[0], bytes[0], bytes[0], bytes[1]],
+        _ => [bytes[0], bytes[0], bytes[0], 255],
+    }
+}
+
+fn encode_pixel(format: D3DFormat, pixel: [u8; 4], data: &mut Vec<u8>) {
+    let [r, g, b, a] = pixel;
+    match format {
+        D3DFormat::A8R8G8B8 => data.extend_from_slice(&[b, g, r, a]),
+        D3DFormat::X8R8G8B8 => data.extend_from_slice(&[b, g, r, 255]),
+        D3DFormat::R5G6B5 => data.extend_from_slice(&rgba_to_rgb565(pixel).to_le_bytes()),
+        D3DFormat::A1R5G5B5 => {
+            let alpha = if a >= 128 { 0x8000 } else { 0 };
+            let packed = alpha | (quantize(r, 5) << 10) | (quantize(g, 5) << 5) | quantize(b, 5);
+            data.extend_from_slice(&packed.to_le_bytes());
+        }
+        D3DFormat::A4R4G4B4 => {
+            let packed = (quantize(a, 4) << 12)
+                | (quantize(r, 4) << 8)
+                | (quantize(g, 4) << 4)
+                | quantize(b, 4);
+            data.extend_from_slice(&packed.to_le_bytes());
+        }
+        D3DFormat::A8L8 => data.extend_from_slice(&[luminance(pixel), a]),
+        _ => data.push(luminance(pixel)),
+    }
+}
+
+// Pixels outside of the image repeat the closest edge pixel
+fn read_block(rgba: &[u8], width: u32, height: u32, x: u32, y: u32) -> Block {
+    std::array::from_fn(|i| {
+        let px = (x + i as u32 % 4).min(width - 1) as usize;
+        let py = (y + i as u32 / 4).min(height - 1) as usize;
+        let offset = (py * width as usize + px) * 4;
+        [
+            rgba[offset],
+            rgba[offset + 1],
+            rgba[offset + 2],
+            rgba[offset + 3],
+        ]
+    })
+}
+
+fn write_block(rgba: &mut [u8], width: u32, height: u32, x: u32, y: u32, block: &Block) {
+    for (i, pixel) in block.iter().enumerate() {
+        let (px, py) = (x + i as u32 % 4, y + i as u32 / 4);
+        if px < width && py < height {
+            let offset = (py as usize * width as usize + px as usize) * 4;
+            rgba[offset..offset + 4].copy_from_slice(pixel);
+        }
+    }
+}
+
+// Decodes a single level into RGBA8
+pub fn decode(format: D3DFormat, width: u32, height: u32, data: &[u8]) -> BffResult<Vec<u8>> {
+    let size = level_size(format, width, height)?;
+    let data = data
+        .get(..size)
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+    let mut rgba = vec![0; width as usize * height as usize * 4];
+
+    if let Some(bytes_per_pixel) = bytes_per_pixel(format) {
+        for (pixel, bytes) in rgba
+            .chunks_exact_mut(4)
+            .zip(data.chunks_exact(bytes_per_pixel))
+        {
+            pixel.copy_from_slice(&decode_pixel(format, bytes));
+        }
+        return Ok(rgba);
+    }
+
+    let block_size = if format == D3DFormat::DXT1 { 8 } else { 16 };
+    let blocks_wide = width.div_ceil(4);
+    for (i, block) in data.chunks_exact(block_size).enumerate() {
+        let pixels = if format == D3DFormat::DXT1 {
+            decode_color_block(block, true)
+        } else {
+            let alpha = if format == D3DFormat::DXT3 {
+                decode_explicit_alpha_block(&block[..8])
+            } else {
+                decode_alpha_block(&block[..8])
+            };
+            let mut pixels = decode_color_block(&block[8..], false);
+            for (pixel, alpha) in pixels.iter_mut().zip(alpha) {
+                pixel[3] = alpha;
+            }
+            pixels
+        };
+        let x = i as u32 % blocks_wide * 4;
+        let y = i as u32 / blocks_wide * 4;
+        write_block(&mut rgba, width, height, x, y, &pixels);
+    }
+
+    Ok(rgba)
+}
+
+// Encodes a single level from RGBA8
+pub fn encode(format: D3DFormat, width: u32, height: u32, rgba: &[u8]) -> BffResult<Vec<u8>> {
+    let mut data = Vec::with_capacity(level_size(format, width, height)?);
+
+    if bytes_per_pixel(format).is_some() {
+        for pixel in rgba.chunks_exact(4) {
+            encode_pixel(format, [pixel[0], pixel[1], pixel[2], pixel[3]], &mut data);
+        }
+        return Ok(data);
+    }
+
+    for y in (0..height).step_by(4) {
+        for x in (0..width).step_by(4) {
+            let pixels = read_block(rgba, width, height, x, y);
+            match format {
+                D3DFormat::DXT1 => data.extend_from_slice(&encode_color_block(&pixels, true)),
+                D3DFormat::DXT3 => {
+                    data.extend_from_slice(&encode_explicit_alpha_block(&pixels));
+                    data.extend_from_slice(&encode_color_block(&pixels, false));
+                }
+                _ => {
+                    data.extend_from_slice(&encode_alpha_block(&pixels));
+                    data.extend_from_slice(&encode_color_block(&pixels, false));
+                }
+            }
+        }
+    }
+
+    Ok(data)
+}
+
+// Box filter, odd dimensions repeat the last row or column
+fn downsample(rgba: &[u8], width: u32, height: u32) -> (Vec<u8>, u32, u32) {
+    let (new_width, new_height) = (mip_dimension(width, 1), mip_dimension(height, 1));
+    let mut downsampled = Vec::with_capacity(new_width as usize * new_height as usize * 4);
+    for y in 0..new_height {
+        for x in 0..new_width {
+            let mut sum = [2u32; 4];
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let px = (x * 2 + dx).min(width - 1) as usize;
+                let py = (y * 2 + dy).min(height - 1) as usize;
+                let offset = (py * width as usize + px) * 4;
+                for (i, sum) in sum.iter_mut().enumerate() {
+                    *sum += rgba[offset + i] as u32;
+                }
+            }
+            downsampled.extend(sum.map(|sum| (sum / 4) as u8));
+        }
+    }
+    (downsampled, new_width, new_height)
+}
+
+pub fn encode_mipmaps(
+    format: D3DFormat,
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+    levels: u32,
+) -> BffResult<Vec<u8>> {
+    let mut data = encode(format, width, height, rgba)?;
+    let (mut level, mut width, mut height) = (rgba.to_vec(), width, height);
+    for _ in 1..levels {
+        (level, width, height) = downsample(&level, width, height);
+        data.extend(encode(format, width, height, &level)?);
+    }
+    Ok(data)
+}
+
+pub fn encode_png(width: u32, height: u32, rgba: &[u8]) -> BffResult<Vec<u8>> {
+    let mut png_data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png_data, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(std::io::Error::other)?;
+    writer
+        .write_image_data(rgba)
+        .map_err(std::io::Error::other)?;
+    writer.finish().map_err(std::io::Error::other)?;
+    Ok(png_data)
+}
+
+pub fn decode_png(png_data: &[u8]) -> BffResult<(u32, u32, Vec<u8>)> {
+    let mut decoder = png::Decoder::new(png_data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|_| Error::ImportBadArtifact)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .map_err(|_| Error::ImportBadArtifact)?;
+    buffer.truncate(info.buffer_size());
+
+    // normalize_to_color8 expands indexed images so only the channel count matters
+    let rgba = match info.color_type.samples() {
+        4 => buffer,
+        3 => buffer
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        2 => buffer
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        _ => buffer.iter().flat_map(|g| [*g, *g, *g, 255]).collect(),
+    };
+
+    Ok((info.width, info.height, rgba))
+}
+
+pub fn encode_png_gray16(width: u32, height: u32, samples: &[u16]) -> BffResult<Vec<u8>> {
+    let mut png_data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png_data, width, height);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Sixteen);
+    let mut writer = encoder.write_header().map_err(std::io::Error::other)?;
+    // PNG stores 16-bit samples big endian
+    let data: Vec<u8> = samples.iter().flat_map(|s| s.to_be_bytes()).collect();
+    writer
+        .write_image_data(&data)
+        .map_err(std::io::Error::other)?;
+    writer.finish().map_err(std::io::Error::other)?;
+    Ok(png_data)
+}
+
+// Only 16-bit grayscale is accepted, an 8-bit image can't say how its samples
+// map to the original range
+pub fn decode_png_gray16(png_data: &[u8]) -> BffResult<(u32, u32, Vec<u16>)> {
+    let mut decoder = png::Decoder::new(png_data);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().map_err(|_| Error::ImportBadArtifact)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .map_err(|_| Error::ImportBadArtifact)?;
+    buffer.truncate(info.buffer_size());
+
+    let samples = match (info.color_type, info.bit_depth) {
+        (png::ColorType::Grayscale, png::BitDepth::Sixteen) => buffer
+            .chunks_exact(2)
+            .map(|s| u16::from_be_bytes([s[0], s[1]]))
+            .collect(),
+        _ => return Err(Error::ImportBadArtifact),
+    };
+
+    Ok((info.width, info.height, samples))
+}
+
+pub fn dds_to_png(dds_data: &[u8]) -> BffResult<Vec<u8>> {
+    let dds = Dds::read(Cursor::new(dds_data)).map_err(|_| Error::UnimplementedImportExport)?;
+    let format = dds
+        .get_d3d_format()
+        .ok_or(Error::UnimplementedImportExport)?;
+    let (width, height) = (dds.get_width(), dds.get_height());
+    let faces = if dds.header.caps2.contains(Caps2::CUBEMAP) {
+        6
+    } else {
+        1
+    };
+    let face_size = chain_size(format, width, height, dds.header.mip_map_count.unwrap_or(1))?;
+
+    let mut rgba = Vec::with_capacity(width as usize * height as usize * 4 * faces);
+    for face in 0..faces {
+        let face_data = dds
+            .data
+            .get(face * face_size..)
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+        rgba.extend(decode(format, width, height, face_data)?);
+    }
+
+    encode_png(width, height * faces as u32, &rgba)
+}
+
+pub fn png_to_dds(
+    png_data: &[u8],
+    format: D3DFormat,
+    mipmap_levels: u32,
+    cubemap: bool,
+) -> BffResult<Dds> {
+    let (width, height, rgba) = decode_png(png_data)?;
+    let faces = if cubemap { 6 } else { 1 };
+    if width == 0 || height == 0 || height % faces != 0 {
+        return Err(Error::ImportBadArtifact);
+    }
+    let face_height = height / faces;
+
+    let mut dds = Dds::new_d3d(NewD3dParams {
+        height: face_height,
+        width,
+        depth: Some(1),
+        format,
+        mipmap_levels: Some(mipmap_levels.max(1)),
+        caps2: cubemap.then_some(Caps2::CUBEMAP | Caps2::CUBEMAP_ALLFACES),
+    })
+    .map_err(|_| Error::ImportBadArtifact)?;
+
+    let face_len = width as usize * face_height as usize * 4;
+    dds.data = Vec::new();
+    for face in rgba.chunks_exact(face_len) {
+        dds.data.extend(encode_mipmaps(
+            format,
+            width,
+            face_height,
+            face,
+            mipmap_levels.max(1),
+        )?);
+    }
+
+    Ok(dds)
+}
+
+// Replaces the pixels of a DDS file with a PNG keeping its format, mipmap
+// count and cubemap layout
+pub fn reencode_dds_from_png(dds_data: &[u8], png_data: &[u8]) -> BffResult<Vec<u8>> {
+    let dds = Dds::read(Cursor::new(dds_data)).map_err(|_| Error::UnimplementedImportExport)?;
+    let format = dds
+        .get_d3d_format()
+        .ok_or(Error::UnimplementedImportExport)?;
+    let dds = png_to_dds(
+        png_data,
+        format,
+        dds.header.mip_map_count.unwrap_or(1),
+        dds.header.caps2.contains(Caps2::CUBEMAP),
+    )?;
+    let mut dds_writer = Cursor::new(Vec::new());
+    dds.write(&mut dds_writer)
+        .map_err(|_| Error::ImportBadArtifact)?;
+    Ok(dds_writer.into_inner())
+}
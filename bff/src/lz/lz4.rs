@@ -44,6 +44,22 @@ pub fn lz4_compress_data_writer(data: &[u8]) -> BinResult<()> {
     Ok(())
 }
 
+// The inverse of lz4_decompress_body_parser. Unlike the data header, the compressed size in the
+// body header includes the header itself.
+#[binrw::writer(writer)]
+pub fn lz4_compress_body_writer(data: &[u8]) -> BinResult<()> {
+    let begin = writer.stream_position()?;
+    writer.seek(SeekFrom::Current(8))?;
+    lz4_compress_data_writer(data, writer, Endian::Little, ())?;
+    let end = writer.stream_position()?;
+
+    writer.seek(SeekFrom::Start(begin))?;
+    (data.len() as u32).write_le(writer)?;
+    ((end - begin) as u32).write_le(writer)?;
+    writer.seek(SeekFrom::Start(end))?;
+    Ok(())
+}
+
 #[binrw::writer(writer)]
 pub fn lz4_compress_data_with_header_writer_internal(data: &[u8]) -> BinResult<()> {
     let begin = writer.stream_position()?;
@@ -31,6 +31,35 @@ macro_rules! bigfiles {
                 }
             }
 
+            pub fn verify_checksums<R: std::io::Read + std::io::Seek>(
+                reader: &mut R,
+                platform: crate::bigfile::platforms::Platform,
+                name_context: &crate::names::NameContext,
+            ) -> crate::BffResult<Vec<crate::bigfile::checksum::BlockChecksumMismatch>> {
+                use crate::bigfile::versions::Version::*;
+                use crate::traits::BigFileIo;
+                use binrw::BinRead;
+
+                let version: crate::bigfile::versions::Version =
+                    crate::helpers::FixedStringNull::<256>::read_be(reader)?
+                        .as_str()
+                        .into();
+                match &version {
+                    $($version_pattern => {
+                        if name_context.name_type() != <$bigfile as BigFileIo>::NAME_TYPE {
+                            return Err(std::io::Error::other(format!(
+                                "NameContext type mismatch: expected {:?}, got {:?}",
+                                <$bigfile as BigFileIo>::NAME_TYPE,
+                                name_context.name_type()
+                            ))
+                            .into());
+                        }
+                        name_context.scope(|| <$bigfile as BigFileIo>::verify_checksums(reader, version, platform))
+                    })*
+                    _ => Err(crate::error::UnimplementedVersionError::new(version).into()),
+                }
+            }
+
             pub fn write<W: std::io::Write + std::io::Seek>(
                 &self,
                 writer: &mut W,
@@ -68,6 +97,38 @@ macro_rules! bigfiles {
 
         }
 
+        impl<R: std::io::Read + std::io::Seek> crate::bigfile::lazy::LazyBigFile<R> {
+            pub fn read_platform(
+                mut reader: R,
+                platform: crate::bigfile::platforms::Platform,
+                name_context: &crate::names::NameContext,
+            ) -> crate::BffResult<Self> {
+                use crate::bigfile::versions::Version::*;
+                use crate::traits::BigFileIo;
+                use binrw::BinRead;
+
+                let version: crate::bigfile::versions::Version =
+                    crate::helpers::FixedStringNull::<256>::read_be(&mut reader)?
+                        .as_str()
+                        .into();
+                let index = match &version {
+                    $($version_pattern => {
+                        if name_context.name_type() != <$bigfile as BigFileIo>::NAME_TYPE {
+                            return Err(std::io::Error::other(format!(
+                                "NameContext type mismatch: expected {:?}, got {:?}",
+                                <$bigfile as BigFileIo>::NAME_TYPE,
+                                name_context.name_type()
+                            ))
+                            .into());
+                        }
+                        name_context.scope(|| <$bigfile as BigFileIo>::read_index(&mut reader, version, platform))?
+                    })*
+                    _ => return Err(crate::error::UnimplementedVersionError::new(version).into()),
+                };
+                Ok(Self::new(reader, index))
+            }
+        }
+
         impl crate::bigfile::resource::Resource {
             pub fn dump_resource<W: std::io::Write + std::io::Seek>(
                 &self,
@@ -1,6 +1,8 @@
-use std::collections::HashMap;
-use std::fmt::Write as _;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter, Write as _};
 use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::Arc;
 
 use encoding_rs::WINDOWS_1252;
 
@@ -9,11 +11,76 @@ use crate::BffResult;
 use crate::class::class_base_names;
 use crate::error::{InvalidNameDecodingError, InvalidNameEncodingError};
 
-pub(super) type NameMap = HashMap<Name, String>;
+// Separates a name file entry from the source it is annotated with
+const SOURCE_ANNOTATION_SEPARATOR: &str = " # ";
 
-fn insert_name(names: &mut NameMap, name_type: NameType, string: &str) -> Name {
+// Where a string in a NameContext came from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NameSource {
+    // Class names and other names that ship with bff
+    Builtin,
+    // A name file, by path
+    File(Arc<str>),
+    Harvested,
+    Cracked,
+    // `$hash$` placeholder strings for names without a known string
+    Forced,
+    // Strings hashed while reading or importing resources, or read without a source
+    Inserted,
+}
+
+impl NameSource {
+    pub fn file(path: &Path) -> Self {
+        Self::File(Arc::from(path.to_string_lossy()))
+    }
+}
+
+impl Display for NameSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Builtin => write!(f, "builtin"),
+            Self::File(path) => write!(f, "{path}"),
+            Self::Harvested => write!(f, "harvested"),
+            Self::Cracked => write!(f, "cracked"),
+            Self::Forced => write!(f, "forced"),
+            Self::Inserted => write!(f, "inserted"),
+        }
+    }
+}
+
+// A string seen for a name and where it came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameEntry {
+    pub string: String,
+    pub source: NameSource,
+}
+
+// Every distinct string seen for each name. The first entry is the one names resolve to.
+pub(super) type NameMap = HashMap<Name, Vec<NameEntry>>;
+
+// The name hashes fold case so strings that only differ by case are the same name and not a
+// collision
+fn insert_entry(names: &mut NameMap, name: Name, string: &str, source: &NameSource) {
+    let entries = names.entry(name).or_default();
+    if !entries
+        .iter()
+        .any(|entry| entry.string.eq_ignore_ascii_case(string))
+    {
+        entries.push(NameEntry {
+            string: string.to_owned(),
+            source: source.clone(),
+        });
+    }
+}
+
+fn insert_name(
+    names: &mut NameMap,
+    name_type: NameType,
+    string: &str,
+    source: &NameSource,
+) -> Name {
     let name = hash_string_for_type(name_type, string);
-    names.entry(name).or_insert_with(|| string.to_owned());
+    insert_entry(names, name, string, source);
     name
 }
 
@@ -21,6 +88,7 @@ fn read_names<R: BufRead>(
     names: &mut NameMap,
     name_type: NameType,
     reader: &mut R,
+    source: &NameSource,
 ) -> BffResult<()> {
     let mut bytes = Vec::new();
     reader.read_to_end(&mut bytes)?;
@@ -43,41 +111,50 @@ fn read_names<R: BufRead>(
 
     for line in cow.lines() {
         if let Some((_, string)) = line.split_once(' ') {
-            insert_name(names, name_type, string.trim_matches('"'));
+            // Annotations written by write_annotated are dropped, the entry is from this source
+            let string = string
+                .rsplit_once(SOURCE_ANNOTATION_SEPARATOR)
+                .filter(|(string, _)| string.ends_with('"'))
+                .map_or(string, |(string, _)| string);
+            insert_name(names, name_type, string.trim_matches('"'), source);
         }
     }
 
     Ok(())
 }
 
-fn write_names<W: Write>(
-    names: &NameMap,
+fn write_names<'a, W: Write>(
+    entries: impl Iterator<Item = (Name, &'a NameEntry)>,
     name_type: NameType,
     writer: &mut W,
     only_names: Option<&[Name]>,
+    annotate: bool,
 ) -> BffResult<()> {
     let mut out = String::new();
-    let mut entries: Vec<(Name, &String)> =
-        names.iter().map(|(name, string)| (*name, string)).collect();
-    entries.sort_unstable_by(|(name_a, string_a), (name_b, string_b)| {
+    let mut entries: Vec<(Name, &NameEntry)> = entries.collect();
+    entries.sort_unstable_by(|(name_a, entry_a), (name_b, entry_b)| {
         name_a
             .as_raw()
             .cmp(&name_b.as_raw())
-            .then_with(|| string_a.cmp(string_b))
+            .then_with(|| entry_a.string.cmp(&entry_b.string))
     });
 
-    for (name, string) in entries {
+    for (name, entry) in entries {
         if let Some(only_names) = only_names
             && !only_names.contains(&name)
         {
             continue;
         }
-        writeln!(
+        write!(
             out,
             r#"{} \"{}\""#,
             name_type.value_string_from_name(name),
-            string
+            entry.string
         )?;
+        if annotate {
+            write!(out, "{SOURCE_ANNOTATION_SEPARATOR}{}", entry.source)?;
+        }
+        writeln!(out)?;
     }
 
     let (cow, encoding_used, had_errors) = WINDOWS_1252.encode(&out);
@@ -114,15 +191,28 @@ impl NameContext {
         for class_name in class_base_names() {
             for style in ALL_NAME_STYLES {
                 let canonical = apply_name_style(class_name, *style);
-                insert_name(&mut names, name_type, canonical.as_str());
+                insert_name(
+                    &mut names,
+                    name_type,
+                    canonical.as_str(),
+                    &NameSource::Builtin,
+                );
             }
         }
 
-        names.insert(Name::default(), String::new());
+        insert_entry(&mut names, Name::default(), "", &NameSource::Builtin);
 
         Self { name_type, names }
     }
 
+    // A NameContext without the builtin names, for working with name files on their own
+    pub fn empty(name_type: NameType) -> Self {
+        Self {
+            name_type,
+            names: NameMap::default(),
+        }
+    }
+
     pub fn into_retyped(self, name_type: NameType) -> Self {
         if self.name_type == name_type {
             return self;
@@ -130,10 +220,9 @@ impl NameContext {
 
         let mut names = self.names;
         let old_names = std::mem::take(&mut names);
-        for string in old_names.into_values() {
-            names
-                .entry(hash_string_for_type(name_type, &string))
-                .or_insert(string);
+        for entry in old_names.into_values().flatten() {
+            let name = hash_string_for_type(name_type, &entry.string);
+            insert_entry(&mut names, name, &entry.string, &entry.source);
         }
 
         Self { name_type, names }
@@ -152,7 +241,16 @@ impl NameContext {
     }
 
     pub fn insert(&mut self, string: &str) -> Name {
-        insert_name(&mut self.names, self.name_type, string)
+        let source = if self.name_type.parse_forced_hash_name(string).is_some() {
+            NameSource::Forced
+        } else {
+            NameSource::Inserted
+        };
+        self.insert_source(string, &source)
+    }
+
+    pub fn insert_source(&mut self, string: &str, source: &NameSource) -> Name {
+        insert_name(&mut self.names, self.name_type, string, source)
     }
 
     pub fn contains(&self, name: Name) -> bool {
@@ -160,14 +258,117 @@ impl NameContext {
     }
 
     pub fn resolve(&self, name: Name) -> Option<String> {
-        self.names.get(&name).cloned()
+        self.entries(name).first().map(|entry| entry.string.clone())
+    }
+
+    // Every string seen for the name, preferred first
+    pub fn entries(&self, name: Name) -> &[NameEntry] {
+        self.names.get(&name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn strings(&self) -> impl Iterator<Item = &str> {
+        self.names
+            .values()
+            .flatten()
+            .map(|entry| entry.string.as_str())
+    }
+
+    // Every source that strings were seen from, in no particular order
+    pub fn sources(&self) -> HashSet<&NameSource> {
+        self.names
+            .values()
+            .flatten()
+            .map(|entry| &entry.source)
+            .collect()
+    }
+
+    // Names that more than one distinct string hashes to. Forced placeholders are not real strings
+    // so they are left out.
+    pub fn collisions(&self) -> impl Iterator<Item = (Name, Vec<&NameEntry>)> {
+        self.names
+            .iter()
+            .map(|(name, entries)| {
+                let entries: Vec<_> = entries
+                    .iter()
+                    .filter(|entry| entry.source != NameSource::Forced)
+                    .collect();
+                (*name, entries)
+            })
+            .filter(|(_, entries)| entries.len() > 1)
+    }
+
+    // Reorder the strings of every name so that those read from earlier sources in the list are
+    // preferred. A source matches if it is the same path or ends with it. Strings from other
+    // sources keep their order after these.
+    pub fn prefer_sources<S: AsRef<Path>>(&mut self, sources: &[S]) {
+        let priority = |entry: &NameEntry| match &entry.source {
+            NameSource::File(path) => sources
+                .iter()
+                .position(|preferred| Path::new(&**path).ends_with(preferred))
+                .unwrap_or(sources.len()),
+            _ => sources.len(),
+        };
+        for entries in self.names.values_mut() {
+            entries.sort_by_cached_key(priority);
+        }
     }
 
     pub fn read<R: BufRead>(&mut self, reader: &mut R) -> BffResult<()> {
-        read_names(&mut self.names, self.name_type, reader)
+        self.read_source(reader, &NameSource::Inserted)
+    }
+
+    pub fn read_source<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        source: &NameSource,
+    ) -> BffResult<()> {
+        read_names(&mut self.names, self.name_type, reader, source)
+    }
+
+    fn preferred_entries(&self) -> impl Iterator<Item = (Name, &NameEntry)> {
+        self.names
+            .iter()
+            .filter_map(|(name, entries)| Some((*name, entries.first()?)))
     }
 
     pub fn write<W: Write>(&self, writer: &mut W, names: Option<&[Name]>) -> BffResult<()> {
-        write_names(&self.names, self.name_type, writer, names)
+        write_names(
+            self.preferred_entries(),
+            self.name_type,
+            writer,
+            names,
+            false,
+        )
+    }
+
+    // Like write but every entry is followed by the source of its string
+    pub fn write_annotated<W: Write>(
+        &self,
+        writer: &mut W,
+        names: Option<&[Name]>,
+    ) -> BffResult<()> {
+        write_names(
+            self.preferred_entries(),
+            self.name_type,
+            writer,
+            names,
+            true,
+        )
+    }
+
+    // Every string seen from `source`, whether or not it is the preferred one
+    pub fn write_source<W: Write>(
+        &self,
+        writer: &mut W,
+        names: Option<&[Name]>,
+        source: &NameSource,
+    ) -> BffResult<()> {
+        let entries = self.names.iter().flat_map(|(name, entries)| {
+            entries
+                .iter()
+                .filter(|entry| entry.source == *source)
+                .map(|entry| (*name, entry))
+        });
+        write_names(entries, self.name_type, writer, names, false)
     }
 }
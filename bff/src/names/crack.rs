@@ -0,0 +1,344 @@
+use std::collections::{HashMap, HashSet};
+use std::str::{Chars, FromStr};
+
+use rayon::iter::{IntoParallelIterator as _, ParallelIterator as _};
+
+use super::{Name, NameContext, NameStyle, NameType, name_style_transform, name_type_style};
+use crate::error::InvalidMaskError;
+
+// Split mask work until there are at least this many items to spread across threads
+const MIN_PARALLEL_ITEMS: usize = 1024;
+
+// Characters that separate the words of a name, used to learn prefixes and suffixes
+const AFFIX_SEPARATORS: &[char] = &['_', '.', '>', '/', '\\', '-', ' '];
+
+// One position of a mask repeated between min and max times
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MaskElement {
+    charset: Vec<u8>,
+    min: usize,
+    max: usize,
+}
+
+// A pattern like `[A-Z0-9_]{1,8}` describing every string of ASCII characters to try. Masks are
+// sequences of literal characters and `[...]` classes each optionally followed by `{n}` or
+// `{min,max}`. `\` escapes the next character.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    elements: Vec<MaskElement>,
+}
+
+impl Mask {
+    // Every way of choosing a repetition count for each element, as the charset of each position
+    fn shapes(&self) -> Vec<Vec<&[u8]>> {
+        self.elements
+            .iter()
+            .fold(vec![Vec::new()], |shapes, element| {
+                shapes
+                    .into_iter()
+                    .flat_map(|shape| {
+                        (element.min..=element.max).map(move |count| {
+                            let mut shape = shape.clone();
+                            shape.extend(std::iter::repeat_n(element.charset.as_slice(), count));
+                            shape
+                        })
+                    })
+                    .collect()
+            })
+    }
+
+    pub fn candidate_count(&self) -> u128 {
+        self.shapes()
+            .iter()
+            .map(|shape| {
+                shape
+                    .iter()
+                    .map(|charset| charset.len() as u128)
+                    .product::<u128>()
+            })
+            .sum()
+    }
+}
+
+fn parse_escaped(chars: &mut Chars<'_>, mask: &str) -> Result<u8, InvalidMaskError> {
+    let c = match chars.next() {
+        Some('\\') => chars.next(),
+        c => c,
+    }
+    .ok_or_else(|| invalid_mask(mask, "unexpected end of mask"))?;
+    u8::try_from(c)
+        .ok()
+        .filter(u8::is_ascii)
+        .ok_or_else(|| invalid_mask(mask, format!("`{c}` is not an ASCII character")))
+}
+
+fn parse_class(chars: &mut Chars<'_>, mask: &str) -> Result<Vec<u8>, InvalidMaskError> {
+    let mut charset = Vec::new();
+    loop {
+        match chars.clone().next() {
+            Some(']') => {
+                chars.next();
+                break;
+            }
+            None => return Err(invalid_mask(mask, "unterminated `[`")),
+            _ => {}
+        }
+        let start = parse_escaped(chars, mask)?;
+        let mut lookahead = chars.clone();
+        if lookahead.next() == Some('-') && lookahead.next().is_some_and(|c| c != ']') {
+            chars.next();
+            let end = parse_escaped(chars, mask)?;
+            if end < start {
+                return Err(invalid_mask(
+                    mask,
+                    format!("range `{}-{}` is reversed", start as char, end as char),
+                ));
+            }
+            charset.extend(start..=end);
+        } else {
+            charset.push(start);
+        }
+    }
+    charset.sort_unstable();
+    charset.dedup();
+    if charset.is_empty() {
+        return Err(invalid_mask(mask, "empty character class"));
+    }
+    Ok(charset)
+}
+
+fn parse_quantifier(chars: &mut Chars<'_>, mask: &str) -> Result<(usize, usize), InvalidMaskError> {
+    if chars.clone().next() != Some('{') {
+        return Ok((1, 1));
+    }
+    chars.next();
+    let mut quantifier = String::new();
+    loop {
+        match chars.next() {
+            Some('}') => break,
+            Some(c) => quantifier.push(c),
+            None => return Err(invalid_mask(mask, "unterminated `{`")),
+        }
+    }
+    let parse = |count: &str| {
+        count
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| invalid_mask(mask, format!("invalid quantifier `{{{quantifier}}}`")))
+    };
+    let (min, max) = match quantifier.split_once(',') {
+        Some((min, max)) => (parse(min)?, parse(max)?),
+        None => {
+            let count = parse(&quantifier)?;
+            (count, count)
+        }
+    };
+    if min > max {
+        return Err(invalid_mask(
+            mask,
+            format!("quantifier `{{{quantifier}}}` has min greater than max"),
+        ));
+    }
+    Ok((min, max))
+}
+
+impl FromStr for Mask {
+    type Err = InvalidMaskError;
+
+    fn from_str(mask: &str) -> Result<Self, Self::Err> {
+        let mut elements = Vec::new();
+        let mut chars = mask.chars();
+        while let Some(c) = chars.clone().next() {
+            let charset = if c == '[' {
+                chars.next();
+                parse_class(&mut chars, mask)?
+            } else {
+                vec![parse_escaped(&mut chars, mask)?]
+            };
+            let (min, max) = parse_quantifier(&mut chars, mask)?;
+            elements.push(MaskElement { charset, min, max });
+        }
+        Ok(Self { elements })
+    }
+}
+
+fn invalid_mask<S: Into<String>>(mask: &str, reason: S) -> InvalidMaskError {
+    InvalidMaskError::new(mask.to_owned(), reason.into())
+}
+
+// Call f with every string made by picking one character for each position
+fn expand(positions: &[&[u8]], candidate: &mut Vec<u8>, f: &mut impl FnMut(&[u8])) {
+    match positions.split_first() {
+        None => f(candidate),
+        Some((charset, rest)) => {
+            for c in *charset {
+                candidate.push(*c);
+                expand(rest, candidate, f);
+                candidate.pop();
+            }
+        }
+    }
+}
+
+// Names in `names` that the NameContext cannot resolve
+pub fn unknown_names(
+    name_context: &NameContext,
+    names: impl IntoIterator<Item = Name>,
+) -> HashSet<Name> {
+    names
+        .into_iter()
+        .filter(|name| !name.is_default() && !name_context.contains(*name))
+        .collect()
+}
+
+// The most common leading and trailing words of known names, e.g. `CHR_` or `_LOD0`, that
+// appear at least twice
+pub fn learn_affixes<'a>(
+    strings: impl IntoIterator<Item = &'a str>,
+    limit: usize,
+) -> (Vec<String>, Vec<String>) {
+    let mut prefixes: HashMap<&str, usize> = HashMap::new();
+    let mut suffixes: HashMap<&str, usize> = HashMap::new();
+    for string in strings {
+        for (i, separator) in string.match_indices(AFFIX_SEPARATORS) {
+            *prefixes.entry(&string[..i + separator.len()]).or_default() += 1;
+            *suffixes.entry(&string[i..]).or_default() += 1;
+        }
+    }
+
+    let most_common = |affixes: HashMap<&str, usize>| {
+        let mut affixes: Vec<_> = affixes
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .collect();
+        affixes.sort_unstable_by(|(a, count_a), (b, count_b)| {
+            count_b.cmp(count_a).then_with(|| a.cmp(b))
+        });
+        affixes
+            .into_iter()
+            .take(limit)
+            .map(|(affix, _)| affix.to_owned())
+            .collect()
+    };
+    (most_common(prefixes), most_common(suffixes))
+}
+
+// Brute-forces the strings behind a set of name hashes. Every candidate is tried with each
+// prefix and suffix, both as is and with each NameStyle transform applied.
+pub struct Cracker {
+    name_type: NameType,
+    targets: HashSet<Name>,
+    prefixes: Vec<String>,
+    suffixes: Vec<String>,
+    styles: Vec<NameStyle>,
+}
+
+impl Cracker {
+    pub fn new(name_type: NameType, targets: HashSet<Name>) -> Self {
+        Self {
+            name_type,
+            targets,
+            prefixes: vec![String::new()],
+            suffixes: vec![String::new()],
+            styles: vec![name_type_style(name_type)],
+        }
+    }
+
+    pub const fn targets(&self) -> &HashSet<Name> {
+        &self.targets
+    }
+
+    pub fn add_prefixes(&mut self, prefixes: impl IntoIterator<Item = String>) {
+        self.prefixes.extend(prefixes);
+        self.prefixes.sort_unstable();
+        self.prefixes.dedup();
+    }
+
+    pub fn add_suffixes(&mut self, suffixes: impl IntoIterator<Item = String>) {
+        self.suffixes.extend(suffixes);
+        self.suffixes.sort_unstable();
+        self.suffixes.dedup();
+    }
+
+    pub fn set_styles(&mut self, styles: Vec<NameStyle>) {
+        self.styles = styles;
+    }
+
+    fn try_candidate(&self, candidate: &str, found: &mut Vec<(Name, String)>) {
+        for prefix in &self.prefixes {
+            for suffix in &self.suffixes {
+                let string = [prefix.as_str(), candidate, suffix.as_str()].concat();
+                let styled = self
+                    .styles
+                    .iter()
+                    .map(|style| name_style_transform(*style)(&string))
+                    .collect::<Vec<_>>();
+                for string in std::iter::once(string).chain(styled) {
+                    let name = self.name_type.hash_bytes(string.as_bytes());
+                    if self.targets.contains(&name) {
+                        found.push((name, string));
+                    }
+                }
+            }
+        }
+    }
+
+    // Every distinct string found for each name, a name can have more than one when strings
+    // collide
+    fn collect(found: impl IntoIterator<Item = (Name, String)>) -> HashMap<Name, Vec<String>> {
+        let mut cracked: HashMap<Name, Vec<String>> = HashMap::new();
+        for (name, string) in found {
+            cracked.entry(name).or_default().push(string);
+        }
+        for strings in cracked.values_mut() {
+            strings.sort_unstable();
+            strings.dedup();
+        }
+        cracked
+    }
+
+    pub fn crack_words(&self, words: &[String]) -> HashMap<Name, Vec<String>> {
+        let found: Vec<_> = words
+            .into_par_iter()
+            .flat_map_iter(|word| {
+                let mut found = Vec::new();
+                self.try_candidate(word, &mut found);
+                found
+            })
+            .collect();
+        Self::collect(found)
+    }
+
+    pub fn crack_mask(&self, mask: &Mask) -> HashMap<Name, Vec<String>> {
+        // Enumerate the first few positions of each shape up front so the rest can be split
+        // across threads
+        let mut work = Vec::new();
+        for shape in mask.shapes() {
+            let mut split = 0;
+            let mut items = 1;
+            while split < shape.len() && items < MIN_PARALLEL_ITEMS {
+                items *= shape[split].len();
+                split += 1;
+            }
+            let (head, tail) = shape.split_at(split);
+            expand(head, &mut Vec::new(), &mut |head| {
+                work.push((head.to_vec(), tail.to_vec()));
+            });
+        }
+
+        let found: Vec<_> = work
+            .into_par_iter()
+            .flat_map_iter(|(head, tail)| {
+                let mut found = Vec::new();
+                let mut candidate = head;
+                expand(&tail, &mut candidate, &mut |candidate| {
+                    // Masks only contain ASCII characters
+                    let candidate = std::str::from_utf8(candidate).unwrap_or_default();
+                    self.try_candidate(candidate, &mut found);
+                });
+                found
+            })
+            .collect();
+        Self::collect(found)
+    }
+}
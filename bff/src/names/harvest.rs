@@ -0,0 +1,105 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{ALL_NAME_STYLES, Name, NameContext, NameType, apply_name_style, json};
+use crate::BffResult;
+
+// Longer strings are prose or script bodies rather than names so only their words are tried
+const MAX_WHOLE_STRING_LENGTH: usize = 256;
+
+const fn is_word_separator(c: char) -> bool {
+    c.is_ascii_whitespace()
+        || c.is_ascii_control()
+        || matches!(
+            c,
+            '"' | '\'' | '`' | ',' | ';' | '=' | '(' | ')' | '[' | ']' | '{' | '}' | '<' | '>'
+        )
+}
+
+// The string itself and the words and path components inside it that could be names
+pub fn string_candidates(string: &str) -> HashSet<&str> {
+    let mut candidates = HashSet::new();
+    let trimmed = string.trim();
+    if trimmed.len() <= MAX_WHOLE_STRING_LENGTH {
+        candidates.insert(trimmed);
+    }
+    for word in string
+        .split(is_word_separator)
+        .filter(|word| !word.is_empty())
+    {
+        candidates.insert(word);
+        let file_name = word.rsplit(['/', '\\']).next().unwrap_or(word);
+        candidates.insert(file_name);
+        if let Some((stem, _)) = file_name.rsplit_once('.') {
+            candidates.insert(stem);
+        }
+    }
+    candidates.remove("");
+    candidates
+}
+
+// Collects strings found in resources and scripts whose hash, as is or with a NameStyle applied,
+// is one of the target names
+pub struct Harvester {
+    name_type: NameType,
+    targets: HashSet<Name>,
+    found: HashMap<Name, String>,
+}
+
+impl Harvester {
+    pub fn new(name_type: NameType, targets: HashSet<Name>) -> Self {
+        Self {
+            name_type,
+            targets,
+            found: HashMap::new(),
+        }
+    }
+
+    pub const fn found(&self) -> &HashMap<Name, String> {
+        &self.found
+    }
+
+    pub fn into_found(self) -> HashMap<Name, String> {
+        self.found
+    }
+
+    fn try_candidate(&mut self, candidate: &str) {
+        let styled = ALL_NAME_STYLES
+            .iter()
+            .map(|style| apply_name_style(candidate, *style));
+        for string in std::iter::once(candidate.to_owned()).chain(styled) {
+            let name = self.name_type.hash_bytes(string.as_bytes());
+            if self.targets.contains(&name) {
+                self.found.entry(name).or_insert(string);
+            }
+        }
+    }
+
+    pub fn harvest_str(&mut self, string: &str) {
+        for candidate in string_candidates(string) {
+            self.try_candidate(candidate);
+        }
+    }
+
+    // Object keys are field names so only values are harvested
+    pub fn harvest_value(&mut self, value: &Value) {
+        match value {
+            Value::String(string) => self.harvest_str(string),
+            Value::Array(values) => values.iter().for_each(|value| self.harvest_value(value)),
+            Value::Object(map) => map.values().for_each(|value| self.harvest_value(value)),
+            _ => {}
+        }
+    }
+
+    // Harvest every string field of anything serializable, such as a class or a manifest
+    pub fn harvest_serialize<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+        name_context: &NameContext,
+    ) -> BffResult<()> {
+        self.harvest_value(&json::to_value(value, name_context)?);
+        Ok(())
+    }
+}
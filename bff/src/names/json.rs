@@ -68,3 +68,10 @@ where
 {
     name_context.scope(|| serde_json::to_string_pretty(value))
 }
+
+pub fn to_value<T>(value: &T, name_context: &NameContext) -> serde_json::Result<Value>
+where
+    T: Serialize + ?Sized,
+{
+    name_context.scope(|| serde_json::to_value(value))
+}
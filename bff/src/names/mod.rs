@@ -1,11 +1,13 @@
 pub mod context;
+pub mod crack;
+pub mod harvest;
 pub mod json;
 mod scope;
 pub mod serde_schema;
 pub mod value;
 pub mod wordlist;
 
-pub use context::NameContext;
+pub use context::{NameContext, NameEntry, NameSource};
 pub use value::{
     Name,
     NameWithContext,
@@ -4,7 +4,7 @@ use std::io::{Read, Seek, Write};
 
 use binrw::{BinRead, BinResult, BinWrite, Endian};
 
-use super::scope::{current_name_type, with_name_context};
+use super::scope::{current_name_type, with_name_context, with_name_context_mut};
 use super::{NameContext, NameType, WORDLIST_BIP39};
 use crate::traits::{NameHashFunction, NameTarget as _};
 
@@ -46,6 +46,26 @@ impl Name {
     pub fn is_default(&self) -> bool {
         *self == Self::default()
     }
+
+    // Resolve against the ambient NameContext. Unresolved names are written as forced hash
+    // strings so they survive a round trip through from_scoped_string.
+    pub fn to_scoped_string(&self) -> Option<String> {
+        with_name_context(|name_context| {
+            let name_context = name_context?;
+            Some(name_context.resolve(*self).unwrap_or_else(|| {
+                get_forced_hash_string_for_type(name_context.name_type(), *self, "")
+            }))
+        })
+    }
+
+    // Hash a string with the ambient NameContext, remembering it if the context is mutable
+    pub fn from_scoped_string<S: AsRef<str>>(string: S) -> Option<Self> {
+        let string = string.as_ref();
+        with_name_context_mut(|name_context| match name_context {
+            Some(name_context) => Some(name_context.insert(string)),
+            None => current_name_type().map(|name_type| hash_string_for_type(name_type, string)),
+        })
+    }
 }
 
 pub struct NameWithContext<'a> {
@@ -0,0 +1,227 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+
+use crate::BffResult;
+use crate::bigfile::BigFile;
+use crate::class::lod::Lod;
+use crate::class::mesh::Mesh;
+use crate::class::node::Node;
+use crate::class::skin::Skin;
+use crate::class::world::World;
+use crate::class::{Class, ClassType};
+use crate::error::Error;
+use crate::helpers::gltf::{self, GltfBuilder};
+use crate::helpers::{MAT4F_IDENTITY, Mat4f, mat4f_flatten, mat4f_inverse, mat4f_mul};
+use crate::names::{Name, NameContext};
+use crate::traits::Artifact;
+
+const GLTF_ARTIFACT_NAME: &str = "scene";
+
+// Walks the Node tree of one or more Worlds and emits a single glTF document with one scene per
+// World. Resources are resolved lazily by following the same names that make up the BigFile
+// reference graph, so missing or unsupported resources are skipped rather than failing the
+// whole export.
+pub struct SceneExporter<'a> {
+    bigfile: &'a BigFile,
+    name_context: &'a NameContext,
+    world_transforms: bool,
+    gltf: GltfBuilder,
+    materials: HashMap<Name, usize>,
+    meshes: HashMap<Name, Vec<usize>>,
+    visited: HashSet<Name>,
+}
+
+impl<'a> SceneExporter<'a> {
+    // With world_transforms each Node's world_transform_mat4 is made relative to its parent
+    // instead of using its rotation, translation, and scale
+    pub fn new(
+        bigfile: &'a BigFile,
+        name_context: &'a NameContext,
+        world_transforms: bool,
+    ) -> Self {
+        Self {
+            bigfile,
+            name_context,
+            world_transforms,
+            gltf: GltfBuilder::new(),
+            materials: HashMap::new(),
+            meshes: HashMap::new(),
+            visited: HashSet::new(),
+        }
+    }
+
+    pub fn world_names(&self) -> BffResult<Vec<Name>> {
+        let name_type = self.bigfile.manifest().version.name_type()?;
+        Ok(self
+            .bigfile
+            .bff_resources()
+            .filter(|bff_resource| {
+                ClassType::from_name_and_type(bff_resource.resource.class_name, name_type)
+                    == Some(ClassType::World)
+            })
+            .map(|bff_resource| bff_resource.resource.name)
+            .collect())
+    }
+
+    fn class(&self, name: Name) -> Option<Class> {
+        if name.is_default() {
+            return None;
+        }
+        let bff_resource = self.bigfile.bff_resource(name)?;
+        bff_resource
+            .bff_class(self.name_context)
+            .ok()
+            .map(|bff_class| bff_class.class)
+    }
+
+    pub fn push_world(&mut self, world_name: Name) -> BffResult<()> {
+        let name_context = self.name_context;
+        name_context.scope(|| {
+            let Some(Class::World(World::WorldV1_381_67_09PC(world))) = self.class(world_name)
+            else {
+                return Err(Error::UnimplementedImportExport);
+            };
+
+            // Worlds can share Nodes, each scene gets its own copy
+            self.visited.clear();
+            let nodes = [world.body.node_name0, world.body.node_name1]
+                .into_iter()
+                .flat_map(|node_name| self.push_siblings(node_name, &MAT4F_IDENTITY))
+                .collect();
+
+            self.gltf.gltf.scenes.push(gltf::Scene {
+                name: world_name.to_scoped_string(),
+                nodes,
+            });
+
+            Ok(())
+        })
+    }
+
+    // Push a Node and all of its next siblings, returning their glTF node indices
+    fn push_siblings(&mut self, node_name: Name, parent_transform: &Mat4f) -> Vec<usize> {
+        let mut nodes = Vec::new();
+        let mut sibling_name = node_name;
+        while let Some((node, next_sibling)) = self.push_node(sibling_name, parent_transform) {
+            nodes.push(node);
+            sibling_name = next_sibling;
+        }
+        nodes
+    }
+
+    fn push_node(&mut self, node_name: Name, parent_transform: &Mat4f) -> Option<(usize, Name)> {
+        // Guard against cycles in malformed sibling and child links
+        if !self.visited.insert(node_name) {
+            return None;
+        }
+        let Some(Class::Node(Node::NodeV1_381_67_09PC(node))) = self.class(node_name) else {
+            return None;
+        };
+        let body = &node.body;
+
+        let mut gltf_node = gltf::Node {
+            name: node_name.to_scoped_string(),
+            ..Default::default()
+        };
+        if self.world_transforms {
+            let local = match mat4f_inverse(parent_transform) {
+                Some(inverse) => mat4f_mul(&body.world_transform_mat4, &inverse),
+                None => body.world_transform_mat4,
+            };
+            gltf_node.matrix = Some(mat4f_flatten(&local));
+        } else {
+            gltf_node.translation = Some(body.translation);
+            gltf_node.rotation = Some(body.rotation);
+            gltf_node.scale = Some([body.scale; 3]);
+        }
+
+        // Nodes can only hold one mesh so any others become children
+        let mut meshes = self.lod_meshes(body.lod_or_particles_name).into_iter();
+        gltf_node.mesh = meshes.next();
+        gltf_node.children = meshes
+            .map(|mesh| {
+                self.gltf.push_node(gltf::Node {
+                    mesh: Some(mesh),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        let children = self.push_siblings(body.head_child_name, &body.world_transform_mat4);
+        gltf_node.children.extend(children);
+
+        Some((self.gltf.push_node(gltf_node), body.next_sibling))
+    }
+
+    // Lods list their detail levels, only the first one that resolves to a Mesh or Skin is used
+    fn lod_meshes(&mut self, lod_name: Name) -> Vec<usize> {
+        let Some(Class::Lod(Lod::LodV1_381_67_09PC(lod))) = self.class(lod_name) else {
+            return Vec::new();
+        };
+        lod.body
+            .skin_or_mesh_or_particles_names
+            .iter()
+            .map(|name| self.meshes(*name))
+            .find(|meshes| !meshes.is_empty())
+            .unwrap_or_default()
+    }
+
+    // Skins are exported in their bind pose without joints since they do not reference their Skel.
+    // Meshes that fail to export are skipped like resources that fail to read.
+    fn meshes(&mut self, name: Name) -> Vec<usize> {
+        if let Some(meshes) = self.meshes.get(&name) {
+            return meshes.clone();
+        }
+        // Claim the name first so that a Skin listing itself cannot recurse forever
+        self.meshes.insert(name, Vec::new());
+
+        let meshes = match self.class(name) {
+            Some(Class::Mesh(Mesh::MeshV1_381_67_09PC(mesh))) => mesh
+                .body
+                .push_gltf_mesh(&mut self.gltf, &mut self.materials, name.to_scoped_string())
+                .ok()
+                .flatten()
+                .into_iter()
+                .collect(),
+            Some(Class::Skin(Skin::SkinV1_381_67_09PC(skin))) => skin
+                .body
+                .mesh_names
+                .iter()
+                .flat_map(|mesh_name| self.meshes(*mesh_name))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        self.meshes.insert(name, meshes.clone());
+        meshes
+    }
+
+    pub fn into_artifacts(self) -> BffResult<HashMap<OsString, Artifact>> {
+        self.gltf.into_artifacts(GLTF_ARTIFACT_NAME)
+    }
+}
+
+// Export the given Worlds, or every World in the BigFile if none are given, as one glTF document
+pub fn export_scene(
+    bigfile: &BigFile,
+    world_names: &[Name],
+    name_context: &NameContext,
+    world_transforms: bool,
+) -> BffResult<HashMap<OsString, Artifact>> {
+    let mut exporter = SceneExporter::new(bigfile, name_context, world_transforms);
+    let world_names = if world_names.is_empty() {
+        exporter.world_names()?
+    } else {
+        world_names.to_vec()
+    };
+
+    // Worlds of other versions are skipped like any other unsupported resource
+    for world_name in world_names {
+        match exporter.push_world(world_name) {
+            Ok(()) | Err(Error::UnimplementedImportExport) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    exporter.into_artifacts()
+}
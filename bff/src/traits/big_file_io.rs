@@ -2,8 +2,11 @@ use std::io::{Read, Seek, Write};
 
 use crate::BffResult;
 use crate::bigfile::BigFile;
+use crate::bigfile::checksum::BlockChecksumMismatch;
+use crate::bigfile::lazy::BigFileIndex;
 use crate::bigfile::platforms::Platform;
 use crate::bigfile::versions::Version;
+use crate::error::UnimplementedVersionError;
 use crate::names::NameType;
 
 pub trait BigFileIo {
@@ -13,6 +16,28 @@ pub trait BigFileIo {
         platform: Platform,
     ) -> BffResult<BigFile>;
 
+    // Backends that can locate resources without decoding them should override this so lazy
+    // readers don't load every resource into memory. Only v2_128_92_19 and v2_256_38_19 do. The
+    // v1 backends keep using this default because their resources are only addressable after
+    // decoding whole blocks and, for pooled resources, the pool, so every resource is decoded up
+    // front and LazyBigFile saves nothing for them.
+    fn read_index<R: Read + Seek>(
+        reader: &mut R,
+        version: Version,
+        platform: Platform,
+    ) -> BffResult<BigFileIndex> {
+        Ok(Self::read(reader, version, platform)?.into())
+    }
+
+    // Only backends whose blocks carry checksums can verify them
+    fn verify_checksums<R: Read + Seek>(
+        _reader: &mut R,
+        version: Version,
+        _platform: Platform,
+    ) -> BffResult<Vec<BlockChecksumMismatch>> {
+        Err(UnimplementedVersionError::new(version).into())
+    }
+
     fn write<W: Write + Seek>(
         bigfile: &BigFile,
         writer: &mut W,
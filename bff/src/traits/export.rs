@@ -7,8 +7,15 @@ use crate::error::Error;
 pub enum Artifact {
     Binary(Vec<u8>),
     Dds(Vec<u8>),
+    Png(Vec<u8>),
     Wav(Vec<u8>),
     Text(String),
+    Gltf(String),
+    GeoJson(String),
+    Svg(String),
+    Obj(String),
+    Fnt(String),
+    Json(String),
 }
 
 pub trait Export {
@@ -7,13 +7,13 @@ use binrw::{BinRead, BinResult, BinWrite, Endian, NullString, args};
 use crate::BffResult;
 use crate::helpers::copy_repeat;
 use crate::lz::{lzo_compress, lzo_decompress};
-use crate::names::{Name, NameContext};
+use crate::names::{Name, NameContext, NameSource};
 
 const DEFAULT_CPS_IN_NAMES: &str = include_str!("ALLSCRIPTS.CPSNameWii");
 
 pub fn read_default_cps_names(name_context: &mut NameContext) -> BffResult<()> {
     let mut reader = crate::BufReader::new(Cursor::new(DEFAULT_CPS_IN_NAMES.as_bytes()));
-    name_context.read(&mut reader)?;
+    name_context.read_source(&mut reader, &NameSource::Builtin)?;
     Ok(())
 }
 
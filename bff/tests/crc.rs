@@ -0,0 +1,76 @@
+use std::ops::ControlFlow;
+
+use bff::crc::{ReversibleCrc32, asobo_alternate32, asobo32, blacksheep32, kalisto32};
+
+const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_";
+const PREFIX: &[u8] = b"data/road_";
+
+const ALGORITHMS: [(ReversibleCrc32, fn(&[u8]) -> i32); 4] = [
+    (ReversibleCrc32::Asobo32, asobo32),
+    (ReversibleCrc32::AsoboAlternate32, asobo_alternate32),
+    (ReversibleCrc32::Kalisto32, kalisto32),
+    (ReversibleCrc32::BlackSheep32, blacksheep32),
+];
+
+fn forge(algorithm: ReversibleCrc32, target: i32, max_length: usize) -> Vec<Vec<u8>> {
+    let mut suffixes = Vec::new();
+    algorithm.forge_suffixes(PREFIX, target, 0, CHARSET, max_length, |suffix| {
+        suffixes.push(suffix.to_vec());
+        ControlFlow::Continue(())
+    });
+    suffixes
+}
+
+#[test]
+fn forged_suffixes_rehash_to_target() {
+    for (algorithm, hash) in ALGORITHMS {
+        let target = hash(b"data/road_N07");
+        let suffixes = forge(algorithm, target, 5);
+        assert!(
+            suffixes
+                .iter()
+                .any(|suffix| suffix.eq_ignore_ascii_case(b"N07")),
+            "{algorithm:?} did not forge the original suffix"
+        );
+        for suffix in &suffixes {
+            assert_eq!(hash(&[PREFIX, suffix].concat()), target, "{algorithm:?}");
+        }
+        assert!(
+            suffixes
+                .windows(2)
+                .all(|pair| pair[0].len() <= pair[1].len())
+        );
+    }
+}
+
+#[test]
+fn forge_empty_suffix() {
+    for (algorithm, hash) in ALGORITHMS {
+        assert_eq!(forge(algorithm, hash(PREFIX), 0), [Vec::<u8>::new()]);
+    }
+}
+
+#[test]
+fn forge_stops_when_callback_breaks() {
+    for (algorithm, hash) in ALGORITHMS {
+        let target = hash(b"data/road_N07");
+        let mut calls = 0;
+        algorithm.forge_suffixes(PREFIX, target, 0, CHARSET, 5, |_| {
+            calls += 1;
+            ControlFlow::Break(())
+        });
+        assert_eq!(calls, 1, "{algorithm:?}");
+    }
+}
+
+#[test]
+fn expected_suffixes() {
+    let algorithm = ReversibleCrc32::Asobo32;
+    assert!(algorithm.expected_suffixes(CHARSET, 6) < 1.0);
+    assert!(algorithm.expected_suffixes(CHARSET, 7) > 1.0);
+    // Both cases of a letter hash the same so they only count once
+    assert_eq!(
+        algorithm.expected_suffixes(b"aA", 32),
+        algorithm.expected_suffixes(b"a", 32)
+    );
+}
@@ -0,0 +1,171 @@
+use bff::helpers::{
+    KeyframerFlag,
+    KeyframerFloat,
+    KeyframerFloatLinear,
+    KeyframerRot,
+    Quat,
+    quat_slerp,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+
+const EPSILON: f32 = 1e-5;
+
+fn from_json<T: DeserializeOwned>(value: Value) -> T {
+    serde_json::from_value(value).unwrap()
+}
+
+fn linear_keys(interpolation_type: &str, keys: &[(f32, f32)]) -> KeyframerFloatLinear {
+    from_json(json!({
+        "interpolation_type": interpolation_type,
+        "keyframes": keys
+            .iter()
+            .map(|(time, value)| json!({ "time": time, "value": value }))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+fn flag_keys(times: &[f32]) -> KeyframerFlag {
+    from_json(json!({
+        "keyframes": times
+            .iter()
+            .enumerate()
+            .map(|(i, time)| json!({ "time": time, "value": i }))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+fn assert_quat_eq(a: Quat, b: Quat) {
+    assert!(
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() < EPSILON),
+        "{a:?} != {b:?}"
+    );
+}
+
+#[test]
+fn sample_clamps_outside_keyframes() {
+    let keyframer = linear_keys("Linear", &[(1., 10.), (3., 30.)]);
+    assert_eq!(keyframer.sample(0.), Some(10.));
+    assert_eq!(keyframer.sample(1.), Some(10.));
+    assert_eq!(keyframer.sample(2.), Some(20.));
+    assert_eq!(keyframer.sample(3.), Some(30.));
+    assert_eq!(keyframer.sample(5.), Some(30.));
+}
+
+#[test]
+fn sample_without_keyframes() {
+    assert_eq!(linear_keys("Linear", &[]).sample(0.), None);
+    assert_eq!(flag_keys(&[]).step(0.), None);
+    assert!(flag_keys(&[]).keyframes_between(0., 1.).is_empty());
+}
+
+#[test]
+fn sample_zero_length_segment() {
+    // Keyframes sharing a time must not divide by zero, the last one wins
+    let keyframer = linear_keys("Linear", &[(0., 0.), (1., 10.), (1., 20.), (2., 40.)]);
+    assert_eq!(keyframer.sample(1.), Some(20.));
+    assert_eq!(keyframer.sample(1.5), Some(30.));
+
+    let keyframer = linear_keys("Linear", &[(1., 10.), (1., 20.)]);
+    assert_eq!(keyframer.sample(0.), Some(10.));
+    assert_eq!(keyframer.sample(1.), Some(20.));
+    assert_eq!(keyframer.sample(2.), Some(20.));
+}
+
+#[test]
+fn sample_square() {
+    let keyframer = linear_keys("Square", &[(1., 10.), (3., 30.)]);
+    assert_eq!(keyframer.sample(0.), Some(10.));
+    assert_eq!(keyframer.sample(2.9), Some(10.));
+    assert_eq!(keyframer.sample(3.), Some(30.));
+    assert_eq!(keyframer.sample(4.), Some(30.));
+}
+
+#[test]
+fn sample_smooth() {
+    let keyframer: KeyframerFloat = from_json(json!({
+        "interpolation_type": "Smooth",
+        "keyframes": [
+            { "time": 0., "value": 10., "tangent_in": 0., "tangent_out": 0. },
+            { "time": 2., "value": 30., "tangent_in": 0., "tangent_out": 0. },
+        ],
+    }));
+    assert_eq!(keyframer.sample(-1.), Some(10.));
+    assert_eq!(keyframer.sample(1.), Some(20.));
+    assert_eq!(keyframer.sample(3.), Some(30.));
+}
+
+#[test]
+fn step() {
+    let keyframer = flag_keys(&[1., 2., 2., 4.]);
+    assert_eq!(keyframer.step(0.), Some(&0));
+    assert_eq!(keyframer.step(1.), Some(&0));
+    assert_eq!(keyframer.step(1.5), Some(&0));
+    assert_eq!(keyframer.step(2.), Some(&2));
+    assert_eq!(keyframer.step(5.), Some(&3));
+}
+
+#[test]
+fn keyframes_between() {
+    let keyframer = flag_keys(&[0., 1., 2., 3.]);
+    let values = |start, end| {
+        keyframer
+            .keyframes_between(start, end)
+            .iter()
+            .map(|key| *key.value())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(values(1., 3.), [1, 2]);
+    assert_eq!(values(-1., 0.5), [0]);
+    assert_eq!(values(3., 10.), [3]);
+    assert!(values(4., 10.).is_empty());
+    assert!(values(2., 2.).is_empty());
+    assert!(values(3., 1.).is_empty());
+}
+
+#[test]
+fn quat_slerp_endpoints_and_midpoint() {
+    let identity = [0., 0., 0., 1.];
+    let half_turn_z = [0., 0., 1., 0.];
+    let quarter_turn_z = [
+        0.,
+        0.,
+        std::f32::consts::FRAC_1_SQRT_2,
+        std::f32::consts::FRAC_1_SQRT_2,
+    ];
+    assert_quat_eq(quat_slerp(identity, half_turn_z, 0.), identity);
+    assert_quat_eq(quat_slerp(identity, half_turn_z, 1.), half_turn_z);
+    assert_quat_eq(quat_slerp(identity, half_turn_z, 0.5), quarter_turn_z);
+}
+
+#[test]
+fn quat_slerp_antipodal() {
+    // q and -q are the same rotation so the interpolation takes the shortest arc
+    let identity = [0., 0., 0., 1.];
+    let negative_identity = [0., 0., 0., -1.];
+    assert_quat_eq(quat_slerp(identity, negative_identity, 0.5), identity);
+
+    let quarter_turn_z = [
+        0.,
+        0.,
+        std::f32::consts::FRAC_1_SQRT_2,
+        std::f32::consts::FRAC_1_SQRT_2,
+    ];
+    let negative_quarter_turn_z = quarter_turn_z.map(|x| -x);
+    assert_quat_eq(
+        quat_slerp(identity, negative_quarter_turn_z, 0.5),
+        quat_slerp(identity, quarter_turn_z, 0.5),
+    );
+}
+
+#[test]
+fn rot_sample_clamps_outside_keyframes() {
+    let keyframer: KeyframerRot = from_json(json!({
+        "keyframes": [
+            { "time": 1., "value": [0., 0., 0., 1.] },
+            { "time": 2., "value": [0., 0., 1., 0.] },
+        ],
+    }));
+    assert_quat_eq(keyframer.sample(0.).unwrap(), [0., 0., 0., 1.]);
+    assert_quat_eq(keyframer.sample(3.).unwrap(), [0., 0., 1., 0.]);
+}
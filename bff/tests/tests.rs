@@ -8,6 +8,9 @@ static GLOBAL: MiMalloc = MiMalloc;
 
 mod bigfile;
 mod cps;
+mod crc;
+mod keyframer;
 mod mqfel_settings;
 mod path_helpers;
+mod texture;
 mod tsc;
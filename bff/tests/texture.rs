@@ -0,0 +1,79 @@
+use bff::helpers::texture::{decode, encode, level_size};
+use ddsfile::D3DFormat;
+
+// Pairs of colors that are exact in RGB565 where the first is no greater than the second in every
+// channel, so a block of only these two colors survives block compression unchanged
+const BLOCK_COLORS: [([u8; 3], [u8; 3]); 4] = [
+    ([0, 0, 0], [255, 255, 255]),
+    ([0, 0, 0], [255, 0, 0]),
+    ([0, 255, 0], [255, 255, 0]),
+    ([0, 0, 255], [0, 255, 255]),
+];
+
+fn image(width: u32, height: u32, pixel: impl Fn(u32, u32) -> [u8; 4]) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height {
+        for x in 0..width {
+            rgba.extend(pixel(x, y));
+        }
+    }
+    rgba
+}
+
+const fn block_color(x: u32, y: u32, alpha: u8) -> [u8; 4] {
+    let (low, high) = BLOCK_COLORS[((y / 4) * 2 + x / 4) as usize % BLOCK_COLORS.len()];
+    let [r, g, b] = if (x ^ y) & 1 == 0 { low } else { high };
+    [r, g, b, alpha]
+}
+
+fn test_image(format: D3DFormat, width: u32, height: u32) -> Vec<u8> {
+    match format {
+        D3DFormat::DXT1 => image(width, height, |x, y| block_color(x, y, 255)),
+        // Explicit alpha is stored in 4 bits
+        D3DFormat::DXT3 => image(width, height, |x, y| {
+            block_color(x, y, ((x + y * width) % 16) as u8 * 17)
+        }),
+        // Interpolated alpha keeps both endpoints of every block exactly
+        D3DFormat::DXT5 => image(width, height, |x, y| {
+            block_color(x, y, if (x + y) % 3 == 0 { 32 } else { 224 })
+        }),
+        D3DFormat::A8L8 => image(width, height, |x, y| {
+            let luminance = (x * 32 + y) as u8;
+            [luminance, luminance, luminance, 255 - (y * 16) as u8]
+        }),
+        _ => unreachable!(),
+    }
+}
+
+#[datatest::data("tests/datasets/texture_roundtrip.yaml")]
+#[test]
+fn roundtrip(format_str: String) {
+    let format = match format_str.as_str() {
+        "DXT1" => D3DFormat::DXT1,
+        "DXT3" => D3DFormat::DXT3,
+        "DXT5" => D3DFormat::DXT5,
+        "A8L8" => D3DFormat::A8L8,
+        _ => panic!("unknown format {format_str}"),
+    };
+
+    // Include sizes that are not a multiple of the block size
+    for (width, height) in [(8, 8), (6, 5), (1, 1)] {
+        let rgba = test_image(format, width, height);
+        let data = encode(format, width, height, &rgba).unwrap();
+        assert_eq!(data.len(), level_size(format, width, height).unwrap());
+        assert!(decode(format, width, height, &data).unwrap() == rgba);
+    }
+}
+
+#[test]
+fn dxt1_transparent_roundtrip() {
+    let rgba = image(6, 5, |x, y| {
+        if (x + y) % 3 == 0 {
+            [0, 0, 0, 0]
+        } else {
+            [255, 255, 255, 255]
+        }
+    });
+    let data = encode(D3DFormat::DXT1, 6, 5, &rgba).unwrap();
+    assert!(decode(D3DFormat::DXT1, 6, 5, &data).unwrap() == rgba);
+}